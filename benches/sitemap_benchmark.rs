@@ -3,7 +3,7 @@
 use criterion::{
     black_box, criterion_group, criterion_main, BatchSize, Criterion,
 };
-use sitemap_gen::{ChangeFreq, SiteMapData, Sitemap};
+use sitemap_gen::{ChangeFreq, CompactSitemap, SiteMapData, Sitemap};
 use url::Url;
 
 fn generate_sitemap(n: usize) -> Sitemap {
@@ -15,6 +15,11 @@ fn generate_sitemap(n: usize) -> Sitemap {
             loc: Url::parse(&format!("{}{}", base_url, i)).unwrap(),
             lastmod: "2023-05-20".to_string(),
             changefreq: ChangeFreq::Weekly,
+            priority: None,
+            extra: Vec::new(),
+            images: Vec::new(),
+            videos: Vec::new(),
+            alternates: Vec::new(),
         };
         sitemap.add_entry(entry).expect("Failed to add entry");
     }
@@ -48,9 +53,77 @@ fn benchmark_sitemap_serialization(c: &mut Criterion) {
     });
 }
 
+fn benchmark_streaming_serialization(c: &mut Criterion) {
+    // A single sitemap holds at most 50,000 entries.
+    let sitemap = generate_sitemap(50_000);
+
+    let mut group = c.benchmark_group("serialization_50k");
+    let _ = group.sample_size(10);
+    let _ = group.bench_function("to_xml", |b| {
+        b.iter(|| black_box(sitemap.to_xml()));
+    });
+    let _ = group.bench_function("write_to", |b| {
+        b.iter(|| black_box(sitemap.write_to(std::io::sink())));
+    });
+    group.finish();
+}
+
+fn generate_entries(n: usize) -> impl Iterator<Item = SiteMapData> {
+    (0..n).map(|i| SiteMapData {
+        loc: Url::parse(&format!("https://example.com/page{}", i))
+            .unwrap(),
+        lastmod: "2023-05-20".to_string(),
+        changefreq: ChangeFreq::Weekly,
+        priority: None,
+        extra: Vec::new(),
+        images: Vec::new(),
+        videos: Vec::new(),
+        alternates: Vec::new(),
+    })
+}
+
+fn benchmark_compact_storage(c: &mut Criterion) {
+    const ENTRIES: usize = 1_000_000;
+
+    let mut group = c.benchmark_group("storage_1m_entries");
+    let _ = group.sample_size(10);
+    let _ = group.bench_function("url_storage", |b| {
+        b.iter(|| {
+            let entries: Vec<SiteMapData> =
+                generate_entries(ENTRIES).collect();
+            black_box(entries)
+        });
+    });
+    let _ = group.bench_function("compact_storage", |b| {
+        b.iter(|| {
+            let mut compact = CompactSitemap::new();
+            generate_entries(ENTRIES)
+                .for_each(|e| compact.add_entry(e));
+            black_box(compact)
+        });
+    });
+    group.finish();
+}
+
+fn benchmark_sitemap_parsing(c: &mut Criterion) {
+    let xml = generate_sitemap(50_000)
+        .to_xml()
+        .expect("Failed to serialize sitemap");
+
+    let _ = c.bench_function("sitemap_parsing_50k", |b| {
+        b.iter(|| {
+            let sitemap = Sitemap::from_xml(black_box(&xml));
+            black_box(sitemap)
+        });
+    });
+}
+
 criterion_group!(
     benches,
     benchmark_sitemap_generation,
-    benchmark_sitemap_serialization
+    benchmark_sitemap_serialization,
+    benchmark_streaming_serialization,
+    benchmark_compact_storage,
+    benchmark_sitemap_parsing
 );
 criterion_main!(benches);
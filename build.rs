@@ -4,8 +4,57 @@
 //! the build script will exit the build process with a non-zero exit code.
 //!
 //! The minimum required version is specified in the `min_version` variable.
+//!
+//! It also records the resolved versions of key dependencies from
+//! `Cargo.lock`, which the CLI reports with `--version-full`.
+
+use std::{env, fs, path::Path, process};
+
+/// Dependencies whose versions are reported by `--version-full`, with the
+/// environment variable each version is exposed as.
+const REPORTED_DEPENDENCIES: [(&str, &str); 3] = [
+    ("url", "SITEMAP_GEN_URL_VERSION"),
+    ("xml-rs", "SITEMAP_GEN_XML_VERSION"),
+    ("dtt", "SITEMAP_GEN_DTT_VERSION"),
+];
 
-use std::process;
+/// Returns the versions of `name` listed in the contents of a `Cargo.lock`.
+fn locked_versions(lock: &str, name: &str) -> Vec<String> {
+    let mut versions = Vec::new();
+    let mut in_package = false;
+    for line in lock.lines().map(str::trim) {
+        if line == "[[package]]" {
+            in_package = false;
+        } else if let Some(value) = line.strip_prefix("name = ") {
+            in_package = value.trim_matches('"') == name;
+        } else if let Some(value) = line.strip_prefix("version = ") {
+            if in_package {
+                versions.push(value.trim_matches('"').to_string());
+            }
+        }
+    }
+    versions
+}
+
+/// Exposes the locked versions of the reported dependencies as
+/// compile-time environment variables, falling back to `unknown`.
+fn emit_dependency_versions() {
+    let lock_path = env::var("CARGO_MANIFEST_DIR")
+        .map(|dir| Path::new(&dir).join("Cargo.lock"))
+        .unwrap_or_else(|_| Path::new("Cargo.lock").to_path_buf());
+    println!("cargo:rerun-if-changed={}", lock_path.display());
+
+    let lock = fs::read_to_string(&lock_path).unwrap_or_default();
+    for (name, var) in REPORTED_DEPENDENCIES {
+        let versions = locked_versions(&lock, name);
+        let version = if versions.is_empty() {
+            "unknown".to_string()
+        } else {
+            versions.join(", ")
+        };
+        println!("cargo:rustc-env={}={}", var, version);
+    }
+}
 
 /// Checks if the current Rustc version is at least the minimum required version
 ///
@@ -16,9 +65,9 @@
 /// # Returns
 ///
 /// * `Some(true)` - If the current Rustc version is at least the minimum
-///    required version.
+///   required version.
 /// * `Some(false)` - If the current Rustc version is less than the minimum
-///    required version.
+///   required version.
 /// * `None` - If the current Rustc version cannot be determined.
 ///
 /// # Errors
@@ -53,4 +102,6 @@ fn main() {
             process::exit(1);
         }
     }
+
+    emit_dependency_versions();
 }
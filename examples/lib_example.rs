@@ -43,6 +43,11 @@ fn create_sitemap_example() -> SitemapResult<()> {
         loc: url,
         lastmod: "2023-10-09".to_string(),
         changefreq: ChangeFreq::Daily,
+        priority: None,
+        extra: Vec::new(),
+        images: Vec::new(),
+        videos: Vec::new(),
+        alternates: Vec::new(),
     };
 
     // Add the site data to the sitemap
@@ -40,6 +40,11 @@ fn generate_sitemap_example() -> Result<(), SitemapError> {
             loc: url,
             lastmod: "2024-10-09".to_string(),
             changefreq: sitemap_gen::ChangeFreq::Weekly,
+            priority: None,
+            extra: Vec::new(),
+            images: Vec::new(),
+            videos: Vec::new(),
+            alternates: Vec::new(),
         })?;
     }
 
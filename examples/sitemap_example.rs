@@ -57,6 +57,11 @@ fn add_entry_to_sitemap_example() -> Result<(), SitemapError> {
         loc: Url::parse("https://example.com")?,
         lastmod: "2023-05-20".to_string(),
         changefreq: ChangeFreq::Weekly,
+        priority: None,
+        extra: Vec::new(),
+        images: Vec::new(),
+        videos: Vec::new(),
+        alternates: Vec::new(),
     };
 
     sitemap.add_entry(entry)?;
@@ -77,6 +82,11 @@ fn convert_sitemap_to_xml_example() -> Result<(), SitemapError> {
         loc: Url::parse("https://example.com")?,
         lastmod: "2023-05-20".to_string(),
         changefreq: ChangeFreq::Weekly,
+        priority: None,
+        extra: Vec::new(),
+        images: Vec::new(),
+        videos: Vec::new(),
+        alternates: Vec::new(),
     })?;
 
     let xml = sitemap.to_xml()?;
@@ -95,6 +105,11 @@ fn sitemap_size_limit_example() -> Result<(), SitemapError> {
             loc: Url::parse(&format!("https://example.com/{}", i))?,
             lastmod: "2023-05-20".to_string(),
             changefreq: ChangeFreq::Weekly,
+            priority: None,
+            extra: Vec::new(),
+            images: Vec::new(),
+            videos: Vec::new(),
+            alternates: Vec::new(),
         })?;
     }
 
@@ -102,6 +117,11 @@ fn sitemap_size_limit_example() -> Result<(), SitemapError> {
         loc: Url::parse("https://example.com/toomany")?,
         lastmod: "2023-05-20".to_string(),
         changefreq: ChangeFreq::Weekly,
+        priority: None,
+        extra: Vec::new(),
+        images: Vec::new(),
+        videos: Vec::new(),
+        alternates: Vec::new(),
     });
 
     match result {
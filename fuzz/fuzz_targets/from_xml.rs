@@ -0,0 +1,16 @@
+//! Fuzzes sitemap parsing: any input must yield `Ok` or `Err`, never a
+//! panic.
+//!
+//! Run with `cargo +nightly fuzz run from_xml` from the repository root.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use sitemap_gen::Sitemap;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Sitemap::from_xml_bytes(data);
+    if let Ok(xml) = std::str::from_utf8(data) {
+        let _ = Sitemap::from_xml(xml);
+    }
+});
@@ -55,12 +55,102 @@ pub enum SitemapError {
     CustomError(String),
 
     /// Error occurred when a sitemap exceeds the maximum allowed size.
-    #[error("Sitemap size exceeds the maximum allowed (10MB)")]
-    SitemapTooLarge,
+    #[error(
+        "Sitemap size ({}) exceeds the maximum allowed ({})",
+        format_size(*.size, *.limit),
+        format_size(*.limit, *.size)
+    )]
+    SitemapTooLarge {
+        /// The size of the serialized sitemap in bytes.
+        size: usize,
+        /// The maximum allowed size in bytes.
+        limit: usize,
+    },
 
     /// Error occurred when the number of URLs in a sitemap exceeds the maximum allowed.
     #[error("Number of URLs ({0}) exceeds the maximum allowed limit (50,000)")]
     MaxUrlLimitExceeded(usize),
+
+    /// Error occurred when a URL is longer than the protocol allows.
+    #[error(
+        "URL length ({len}) exceeds the maximum allowed (2,048): {url}"
+    )]
+    UrlTooLong {
+        /// The offending URL.
+        url: String,
+        /// The length of the URL in bytes.
+        len: usize,
+    },
+
+    /// Error occurred when a URL is added twice to a sitemap that rejects
+    /// duplicates.
+    #[error("Duplicate URL: {0}")]
+    DuplicateUrl(String),
+
+    /// Error occurred when a single entry is too large for any sitemap.
+    #[error("Entry for {url} needs {size} bytes, more than fits in a single sitemap (10MB)")]
+    UrlTooLarge {
+        /// The location of the offending entry.
+        url: String,
+        /// The size of a sitemap holding only this entry, in bytes.
+        size: usize,
+    },
+
+    /// Error occurred when a priority falls outside the 0.0 to 1.0 range.
+    #[error("Invalid priority: {0} (must be between 0.0 and 1.0)")]
+    InvalidPriority(f32),
+
+    /// Error occurred when an entry holds more images than Google accepts.
+    #[cfg(feature = "image-sitemap")]
+    #[error("URL {url} has {count} images (maximum is 1,000)")]
+    TooManyImages {
+        /// The location of the offending entry.
+        url: String,
+        /// The number of images on the entry.
+        count: usize,
+    },
+
+    /// Error occurred when an alternate has a malformed `hreflang` code.
+    #[error("Invalid hreflang '{0}' (expected 'xx', 'xx-YY' or 'x-default')")]
+    InvalidHreflang(String),
+
+    /// Error occurred when a video has no location to play it from.
+    #[error("Video '{title}' on {url} has neither a content_loc nor a player_loc")]
+    MissingVideoLocation {
+        /// The location of the page holding the video.
+        url: String,
+        /// The title of the offending video.
+        title: String,
+    },
+
+    /// Error occurred when text holds a character that XML 1.0 doesn't allow.
+    #[error("Invalid XML character U+{:04X} in <{element}>", *.character as u32)]
+    InvalidXmlCharacter {
+        /// The offending character.
+        character: char,
+        /// The name of the element holding the character.
+        element: String,
+    },
+
+    /// Error occurred when a character cannot be represented in the output encoding.
+    #[error("Character '{character}' cannot be encoded in {encoding}")]
+    UnencodableCharacter {
+        /// The character that cannot be encoded.
+        character: char,
+        /// The name of the output encoding.
+        encoding: &'static str,
+    },
+
+    /// Error occurred when strict serialization found an invalid `lastmod`.
+    #[error("Invalid lastmod '{lastmod}' in entry {index} ({url})")]
+    InvalidLastmod {
+        /// The position of the offending entry in the sitemap.
+        index: usize,
+        /// The location of the offending entry.
+        url: String,
+        /// The invalid `lastmod` value.
+        lastmod: String,
+    },
 }
 
 impl SitemapError {
@@ -82,12 +172,93 @@ pub fn context(&self) -> &'static str {
             SitemapError::EncodingError(_) => "Error occurred during UTF-8 string encoding or decoding",
             SitemapError::InvalidChangeFreq(_) => "An invalid change frequency value was provided",
             SitemapError::CustomError(_) => "An unexpected error occurred",
-            SitemapError::SitemapTooLarge => "The generated sitemap exceeds the maximum allowed size",
+            SitemapError::SitemapTooLarge { .. } => "The generated sitemap exceeds the maximum allowed size",
             SitemapError::MaxUrlLimitExceeded(_) => "The number of URLs exceeds the maximum allowed limit",
+            SitemapError::UrlTooLong { .. } => "A URL exceeds the maximum allowed length",
+            SitemapError::DuplicateUrl(_) => "A URL was added to the sitemap twice",
+            SitemapError::UrlTooLarge { .. } => "A single entry exceeds the maximum sitemap size",
+            SitemapError::InvalidPriority(_) => "A priority outside the 0.0 to 1.0 range was provided",
+            #[cfg(feature = "image-sitemap")]
+            SitemapError::TooManyImages { .. } => "An entry holds more images than allowed",
+            SitemapError::InvalidHreflang(_) => "An alternate has a malformed language code",
+            SitemapError::MissingVideoLocation { .. } => "A video has neither a content nor a player location",
+            SitemapError::InvalidXmlCharacter { .. } => "Text holds a character not allowed in XML",
+            SitemapError::UnencodableCharacter { .. } => "A character cannot be represented in the output encoding",
+            SitemapError::InvalidLastmod { .. } => "An entry has an invalid last modification date",
         }
     }
 }
 
+/// Formats `bytes` for comparison with `other`: as megabytes, or as an
+/// exact byte count if both round to the same number of megabytes.
+fn format_size(bytes: usize, other: usize) -> String {
+    let megabytes = format_megabytes(bytes);
+    if bytes != other && megabytes == format_megabytes(other) {
+        format!("{} bytes", bytes)
+    } else {
+        megabytes
+    }
+}
+
+/// Formats a byte count as megabytes with at most one decimal place.
+fn format_megabytes(bytes: usize) -> String {
+    let megabytes = format!("{:.1}", bytes as f64 / (1024.0 * 1024.0));
+    match megabytes.strip_suffix(".0") {
+        Some(whole) => format!("{}MB", whole),
+        None => format!("{}MB", megabytes),
+    }
+}
+
+/// A problem found while validating a single sitemap entry.
+///
+/// Unlike `SitemapError`, a `ValidationIssue` does not abort an operation;
+/// it describes why a particular entry would not produce a valid sitemap,
+/// so callers can report or discard the offending entries.
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ValidationIssue {
+    /// The `lastmod` value is not a valid W3C date or datetime.
+    #[error("Invalid lastmod date: '{0}'")]
+    InvalidLastmod(String),
+
+    /// The URL is longer than the 2,048 characters allowed by the protocol.
+    #[error("URL length ({0}) exceeds the maximum allowed (2,048)")]
+    UrlTooLong(usize),
+
+    /// The URL does not use the http or https scheme.
+    #[error("Unsupported URL scheme: {0}")]
+    InvalidScheme(String),
+
+    /// The URL is already present in the sitemap.
+    #[error("Duplicate URL: {0}")]
+    Duplicate(String),
+
+    /// The sitemap already holds the 50,000 URLs allowed by the protocol.
+    #[error(
+        "Sitemap already holds the maximum number of URLs (50,000)"
+    )]
+    SitemapFull,
+
+    /// The URL is not under the directory of the sitemap's location.
+    #[error("URL is outside the sitemap's scope: {0}")]
+    OutOfScope(String),
+
+    /// The URL contains a `%` not followed by two hexadecimal digits.
+    #[error("URL contains a malformed percent-escape: {0}")]
+    MalformedEscape(String),
+
+    /// The entry holds more images than the configured limit, 1,000 by
+    /// default.
+    #[cfg(feature = "image-sitemap")]
+    #[error("Entry {index} has too many images ({count})")]
+    TooManyImages {
+        /// The position of the entry in the sitemap.
+        index: usize,
+        /// The number of images the entry holds.
+        count: usize,
+    },
+}
+
 /// Custom result type for sitemap operations.
 ///
 /// This type alias simplifies the return types of functions that can produce
@@ -177,10 +348,13 @@ fn test_error_display() {
             "Custom error: Custom error message"
         );
 
-        let sitemap_too_large = SitemapError::SitemapTooLarge;
+        let sitemap_too_large = SitemapError::SitemapTooLarge {
+            size: 12_897_485,
+            limit: 10 * 1024 * 1024,
+        };
         assert_eq!(
             sitemap_too_large.to_string(),
-            "Sitemap size exceeds the maximum allowed (10MB)"
+            "Sitemap size (12.3MB) exceeds the maximum allowed (10MB)"
         );
 
         let max_url_limit_exceeded =
@@ -250,11 +424,18 @@ fn test_encoding_error() {
 
     #[test]
     fn test_sitemap_size_errors() {
-        let sitemap_too_large = SitemapError::SitemapTooLarge;
+        let sitemap_too_large = SitemapError::SitemapTooLarge {
+            size: 10 * 1024 * 1024 + 1,
+            limit: 10 * 1024 * 1024,
+        };
         assert_eq!(
             sitemap_too_large.to_string(),
-            "Sitemap size exceeds the maximum allowed (10MB)"
+            "Sitemap size (10485761 bytes) exceeds the maximum allowed (10485760 bytes)"
         );
+        assert!(matches!(
+            sitemap_too_large,
+            SitemapError::SitemapTooLarge { size, limit } if size > limit
+        ));
         assert_eq!(
             sitemap_too_large.context(),
             "The generated sitemap exceeds the maximum allowed size"
@@ -329,6 +510,54 @@ fn test_invalid_change_freq_edge_cases() {
             .contains("Invalid change frequency"));
     }
 
+    #[test]
+    fn test_validation_issue_display() {
+        assert_eq!(
+            ValidationIssue::InvalidLastmod("yesterday".to_string())
+                .to_string(),
+            "Invalid lastmod date: 'yesterday'"
+        );
+        assert_eq!(
+            ValidationIssue::UrlTooLong(3000).to_string(),
+            "URL length (3000) exceeds the maximum allowed (2,048)"
+        );
+        assert_eq!(
+            ValidationIssue::InvalidScheme("ftp".to_string())
+                .to_string(),
+            "Unsupported URL scheme: ftp"
+        );
+        assert_eq!(
+            ValidationIssue::Duplicate(
+                "https://example.com/".to_string()
+            )
+            .to_string(),
+            "Duplicate URL: https://example.com/"
+        );
+        assert_eq!(
+            ValidationIssue::OutOfScope(
+                "https://x.com/other".to_string()
+            )
+            .to_string(),
+            "URL is outside the sitemap's scope: https://x.com/other"
+        );
+        assert_eq!(
+            ValidationIssue::MalformedEscape(
+                "https://x.com/%2".to_string()
+            )
+            .to_string(),
+            "URL contains a malformed percent-escape: https://x.com/%2"
+        );
+        #[cfg(feature = "image-sitemap")]
+        assert_eq!(
+            ValidationIssue::TooManyImages {
+                index: 3,
+                count: 1_001
+            }
+            .to_string(),
+            "Entry 3 has too many images (1001)"
+        );
+    }
+
     #[test]
     fn test_max_url_limit_exceeded_edge_cases() {
         let just_under_limit = SitemapError::MaxUrlLimitExceeded(49999);
@@ -30,10 +30,20 @@
 pub mod utils;
 
 // Re-exports
-pub use error::SitemapError;
+pub use error::{SitemapError, ValidationIssue};
+pub use sitemap::alternate::Alternate;
+pub use sitemap::compact::{CompactEntry, CompactSitemap};
+pub use sitemap::image::ImageEntry;
+pub use sitemap::incremental::IncrementalSitemapWriter;
+pub use sitemap::index::{SitemapIndex, SitemapIndexEntry};
+pub use sitemap::video::VideoEntry;
+pub use sitemap::writer::SitemapWriter;
+#[cfg(feature = "image-sitemap")]
+pub use sitemap::ExtensionLimits;
 pub use sitemap::{
-    convert_date_format, create_site_map_data, ChangeFreq, SiteMapData,
-    Sitemap,
+    convert_date_format, create_site_map_data, ChangeFreq,
+    ChangeFreqAliases, ExtraElement, LastmodPrecision, ParseOptions,
+    SiteMapData, Sitemap, XmlConfig,
 };
 
 /// Result type alias for sitemap operations.
@@ -41,7 +51,7 @@
 
 /// A prelude module for convenient importing of commonly used items.
 pub mod prelude {
-    pub use crate::error::SitemapError;
+    pub use crate::error::{SitemapError, ValidationIssue};
     pub use crate::sitemap::{ChangeFreq, SiteMapData, Sitemap};
     pub use crate::SitemapResult;
 }
@@ -66,6 +76,11 @@ fn test_create_sitemap() {
                 .expect("Failed to parse URL"),
             lastmod: "2024-10-08".to_string(),
             changefreq: ChangeFreq::Daily,
+            priority: None,
+            extra: Vec::new(),
+            images: Vec::new(),
+            videos: Vec::new(),
+            alternates: Vec::new(),
         };
 
         // Add the entry to the sitemap
@@ -85,6 +100,11 @@ fn test_serialize_sitemap() {
                 .expect("Failed to parse URL"),
             lastmod: "2024-10-08".to_string(),
             changefreq: ChangeFreq::Daily,
+            priority: None,
+            extra: Vec::new(),
+            images: Vec::new(),
+            videos: Vec::new(),
+            alternates: Vec::new(),
         };
 
         sitemap.add_entry(entry).expect("Failed to add entry");
@@ -111,6 +131,11 @@ fn test_invalid_url_error() {
                 loc: valid_url,
                 lastmod: "2024-10-08".to_string(),
                 changefreq: ChangeFreq::Daily,
+                priority: None,
+                extra: Vec::new(),
+                images: Vec::new(),
+                videos: Vec::new(),
+                alternates: Vec::new(),
             }),
             Err(e) => Err(SitemapError::UrlError(e)),
         };
@@ -142,6 +167,11 @@ fn test_sitemap_data_creation() {
                 .expect("Failed to parse URL"),
             lastmod: "2024-10-08".to_string(),
             changefreq: ChangeFreq::Daily,
+            priority: None,
+            extra: Vec::new(),
+            images: Vec::new(),
+            videos: Vec::new(),
+            alternates: Vec::new(),
         };
 
         // Create an empty sitemap and add the entry
@@ -192,6 +222,11 @@ fn test_valid_url_addition() {
             loc: valid_url,
             lastmod: "2024-10-08".to_string(),
             changefreq: ChangeFreq::Daily,
+            priority: None,
+            extra: Vec::new(),
+            images: Vec::new(),
+            videos: Vec::new(),
+            alternates: Vec::new(),
         });
 
         // Assert that the entry was successfully added
@@ -38,7 +38,7 @@
 //! sitemap-gen generate -o output.xml -i urls.txt -c daily -v
 //! ```
 
-use sitemap_gen::utils::{create_cli, generate_sitemap};
+use sitemap_gen::utils::{create_cli, generate_sitemap, version_full};
 use sitemap_gen::SitemapResult;
 
 /// The main entry point for the Sitemap Generator CLI.
@@ -58,6 +58,11 @@ fn main() -> SitemapResult<()> {
 
     let matches = create_cli().get_matches();
 
+    if matches.get_flag("version-full") {
+        println!("{}", version_full());
+        return Ok(());
+    }
+
     if let Some(matches) = matches.subcommand_matches("generate") {
         generate_sitemap(matches)?;
     }
@@ -1,18 +1,81 @@
 // src/sitemap.rs
 
-use crate::error::{SitemapError, SitemapResult};
+use crate::error::{SitemapError, SitemapResult, ValidationIssue};
+use crate::sitemap::alternate::{
+    check_hreflang, write_alternate, Alternate, XHTML_NS,
+};
+use crate::sitemap::image::ImageEntry;
+#[cfg(feature = "image-sitemap")]
+use crate::sitemap::image::{
+    write_image, IMAGE_NS, MAX_IMAGES_PER_URL,
+};
+use crate::sitemap::index::SitemapIndex;
+use crate::sitemap::video::{
+    check_video, write_video, VideoEntry, VIDEO_NS,
+};
 use dtt::datetime::DateTime;
 use lazy_static::lazy_static;
+use log::warn;
 use regex::Regex;
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
+use std::fs;
+use std::hash::Hash;
+use std::io::{BufRead, Write};
+use std::path::Path;
 use std::str::FromStr;
+use std::sync::Arc;
 use url::Url;
-use xml::writer::{EventWriter, XmlEvent};
+use xml::reader::{EventReader, XmlEvent as ReaderEvent};
+use xml::writer::{EmitterConfig, EventWriter, XmlEvent};
+
+/// Localized alternates of a page, written as `<xhtml:link>` elements.
+pub mod alternate;
+
+/// A memory-efficient sitemap representation for very large URL sets.
+pub mod compact;
+
+/// Google's image sitemap extension.
+pub mod image;
+
+/// Writes sitemaps to disk entry by entry as URLs are discovered.
+pub mod incremental;
+
+/// Sitemap index files referencing several child sitemaps.
+pub mod index;
+
+mod sha256;
+
+/// Google's video sitemap extension.
+pub mod video;
+
+/// A low-level writer for building custom sitemap documents.
+pub mod writer;
 
 /// Maximum number of URLs allowed in a sitemap.
 const MAX_URLS: usize = 50_000;
 
+/// Maximum size of a serialized sitemap in bytes (10MB).
+pub const MAX_SITEMAP_SIZE: usize = 10 * 1024 * 1024;
+
+/// Maximum length of a URL allowed in a sitemap.
+const MAX_URL_LENGTH: usize = 2_048;
+
+/// File extensions of non-HTML resources that change less often than pages.
+const DOCUMENT_EXTENSIONS: &[&str] = &[
+    "pdf", "doc", "docx", "xls", "xlsx", "ppt", "pptx", "odt", "txt",
+    "jpg", "jpeg", "png", "gif", "webp", "svg",
+];
+
+/// The priority `add_url` gives to URLs with a [`DOCUMENT_EXTENSIONS`]
+/// extension, ranking them below pages.
+const DOCUMENT_PRIORITY: f32 = 0.3;
+
+/// The XML namespace of the sitemaps.org protocol.
+const SITEMAP_NS: &str = "http://www.sitemaps.org/schemas/sitemap/0.9";
+
 /// Represents the data for a sitemap entry.
 #[derive(Debug, Clone, PartialEq)]
 pub struct SiteMapData {
@@ -22,13 +85,233 @@ pub struct SiteMapData {
     pub lastmod: String,
     /// The location (URL) of the page.
     pub loc: Url,
+    /// The priority of the URL relative to other URLs on the site, from
+    /// 0.0 to 1.0. No `<priority>` element is written when `None`.
+    pub priority: Option<f32>,
+    /// Additional child elements written inside `<url>`, for extensions
+    /// this crate doesn't model.
+    pub extra: Vec<ExtraElement>,
+    /// Images on the page, written with Google's image sitemap extension
+    /// when the `image-sitemap` feature is enabled, and ignored otherwise.
+    /// At most 1,000 images are allowed per URL.
+    pub images: Vec<ImageEntry>,
+    /// Videos on the page, written with Google's video sitemap extension.
+    pub videos: Vec<VideoEntry>,
+    /// Localized versions of the page, written as `<xhtml:link>` elements
+    /// with their `hreflang`.
+    pub alternates: Vec<Alternate>,
+}
+
+/// An extension element written inside `<url>` as `<name>value</name>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtraElement {
+    /// The element name, such as `note`, or `mobile:mobile` for a prefixed
+    /// element.
+    pub name: String,
+    /// The element text, or an empty string for an empty element.
+    pub value: String,
+    /// The namespace bound to the prefix of `name`. Prefixed elements must
+    /// have one, and unprefixed elements must not.
+    pub namespace: Option<String>,
+}
+
+impl SiteMapData {
+    /// Creates an entry without a priority.
+    ///
+    /// # Arguments
+    /// * `loc` - The location (URL) of the page.
+    /// * `lastmod` - The last modification date of the page.
+    /// * `changefreq` - How often the page is expected to change.
+    pub fn new(
+        loc: Url,
+        lastmod: impl Into<String>,
+        changefreq: ChangeFreq,
+    ) -> Self {
+        SiteMapData {
+            changefreq,
+            lastmod: lastmod.into(),
+            loc,
+            priority: None,
+            extra: Vec::new(),
+            images: Vec::new(),
+            videos: Vec::new(),
+            alternates: Vec::new(),
+        }
+    }
+
+    /// Adds an extension element, written as `<name>value</name>`.
+    ///
+    /// Use [`SiteMapData::with_extra_ns`] for prefixed elements.
+    ///
+    /// # Arguments
+    /// * `name` - The element name, without a prefix.
+    /// * `value` - The element text, or an empty string for an empty element.
+    ///
+    /// # Returns
+    /// The updated entry, or an error if `name` is not a valid XML name.
+    pub fn with_extra(
+        mut self,
+        name: impl Into<String>,
+        value: impl Into<String>,
+    ) -> SitemapResult<Self> {
+        let element = ExtraElement {
+            name: name.into(),
+            value: value.into(),
+            namespace: None,
+        };
+        check_extra(&element)?;
+        self.extra.push(element);
+        Ok(self)
+    }
+
+    /// Adds a prefixed extension element, written as
+    /// `<prefix:name>value</prefix:name>`.
+    ///
+    /// The prefix is bound to `namespace` on the `<urlset>` element, or on
+    /// the element itself when written with a [`SitemapWriter`](crate::SitemapWriter).
+    ///
+    /// # Arguments
+    /// * `prefix` - The namespace prefix, such as `mobile`.
+    /// * `namespace` - The namespace URI the prefix stands for.
+    /// * `name` - The local element name.
+    /// * `value` - The element text, or an empty string for an empty element.
+    ///
+    /// # Returns
+    /// The updated entry, or an error if the prefix or name is not a valid
+    /// XML name, or the prefix is reserved.
+    pub fn with_extra_ns(
+        mut self,
+        prefix: &str,
+        namespace: impl Into<String>,
+        name: &str,
+        value: impl Into<String>,
+    ) -> SitemapResult<Self> {
+        let element = ExtraElement {
+            name: format!("{}:{}", prefix, name),
+            value: value.into(),
+            namespace: Some(namespace.into()),
+        };
+        check_extra(&element)?;
+        self.extra.push(element);
+        Ok(self)
+    }
+
+    /// Adds an image to the entry.
+    ///
+    /// # Arguments
+    /// * `image` - The image to add.
+    ///
+    /// # Returns
+    /// The updated entry, or an error if the entry already holds 1,000
+    /// images.
+    #[cfg(feature = "image-sitemap")]
+    pub fn with_image(
+        mut self,
+        image: ImageEntry,
+    ) -> SitemapResult<Self> {
+        if self.images.len() >= MAX_IMAGES_PER_URL {
+            return Err(SitemapError::TooManyImages {
+                url: self.loc.to_string(),
+                count: self.images.len() + 1,
+            });
+        }
+        self.images.push(image);
+        Ok(self)
+    }
+
+    /// Adds a video to the entry.
+    ///
+    /// # Arguments
+    /// * `video` - The video to add.
+    ///
+    /// # Returns
+    /// The updated entry, or `SitemapError::MissingVideoLocation` if the
+    /// video has neither a `content_loc` nor a `player_loc`.
+    pub fn with_video(
+        mut self,
+        video: VideoEntry,
+    ) -> SitemapResult<Self> {
+        check_video(self.loc.as_str(), &video)?;
+        self.videos.push(video);
+        Ok(self)
+    }
+
+    /// Adds a localized alternate of the page.
+    ///
+    /// # Arguments
+    /// * `alternate` - The alternate to add.
+    ///
+    /// # Returns
+    /// The updated entry, or `SitemapError::InvalidHreflang` if the
+    /// alternate's language code is malformed.
+    pub fn with_alternate(
+        mut self,
+        alternate: Alternate,
+    ) -> SitemapResult<Self> {
+        check_hreflang(&alternate.hreflang)?;
+        self.alternates.push(alternate);
+        Ok(self)
+    }
+
+    /// Sets the priority of the entry.
+    ///
+    /// # Arguments
+    /// * `priority` - The priority, between 0.0 and 1.0 inclusive.
+    ///
+    /// # Returns
+    /// The updated entry, or `SitemapError::InvalidPriority` if the value is
+    /// out of range.
+    pub fn with_priority(
+        mut self,
+        priority: f32,
+    ) -> SitemapResult<Self> {
+        self.priority = Some(check_priority(priority)?);
+        Ok(self)
+    }
+
+    /// Checks the entry against the sitemap protocol rules.
+    ///
+    /// # Returns
+    /// `Ok(())` if the entry is valid, or the first `ValidationIssue` found.
+    pub fn validate(&self) -> Result<(), ValidationIssue> {
+        if !matches!(self.loc.scheme(), "http" | "https") {
+            return Err(ValidationIssue::InvalidScheme(
+                self.loc.scheme().to_string(),
+            ));
+        }
+        let len = self.loc.as_str().len();
+        if len > MAX_URL_LENGTH {
+            return Err(ValidationIssue::UrlTooLong(len));
+        }
+        if has_malformed_escape(self.loc.as_str()) {
+            return Err(ValidationIssue::MalformedEscape(
+                self.loc.to_string(),
+            ));
+        }
+        if self.lastmod_as_datetime().is_none() {
+            return Err(ValidationIssue::InvalidLastmod(
+                self.lastmod.clone(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Parses the stored `lastmod` string into a `DateTime`.
+    ///
+    /// Both plain dates (`2024-10-08`) and RFC 3339 datetimes are accepted.
+    ///
+    /// # Returns
+    /// The parsed `DateTime`, or `None` if `lastmod` can't be parsed.
+    pub fn lastmod_as_datetime(&self) -> Option<DateTime> {
+        DateTime::parse(&self.lastmod).ok()
+    }
 }
 
 /// Represents the change frequency of a URL in the sitemap.
 ///
 /// This enum is used to indicate how frequently the page is likely to change.
 /// Search engines use this information when deciding how often to crawl the page.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub enum ChangeFreq {
     /// The page is changed every time it's accessed.
     Always,
@@ -37,6 +320,7 @@ pub enum ChangeFreq {
     /// The page is changed every day.
     Daily,
     /// The page is changed every week.
+    #[default]
     Weekly,
     /// The page is changed every month.
     Monthly,
@@ -47,8 +331,15 @@ pub enum ChangeFreq {
 }
 
 impl ChangeFreq {
+    /// The string representations of all change frequencies, in the same
+    /// order as the enum variants.
+    pub const VARIANTS: &'static [&'static str] = &[
+        "always", "hourly", "daily", "weekly", "monthly", "yearly",
+        "never",
+    ];
+
     /// Returns the string representation of the change frequency.
-    pub fn as_str(&self) -> &'static str {
+    pub const fn as_str(&self) -> &'static str {
         match self {
             ChangeFreq::Always => "always",
             ChangeFreq::Hourly => "hourly",
@@ -78,18 +369,53 @@ fn from_str(s: &str) -> Result<Self, Self::Err> {
     }
 }
 
+/// A configurable map of alternative names for change frequencies.
+///
+/// Some systems export values such as `realtime` or `nightly` that are not
+/// part of the sitemaps.org vocabulary. Registering them here lets them be
+/// parsed into a `ChangeFreq`, while unknown values still produce an error.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ChangeFreqAliases {
+    aliases: HashMap<String, ChangeFreq>,
+}
+
+impl ChangeFreqAliases {
+    /// Creates an empty alias map.
+    pub fn new() -> Self {
+        ChangeFreqAliases {
+            aliases: HashMap::new(),
+        }
+    }
+
+    /// Registers `alias` as an alternative name for `freq`.
+    ///
+    /// Aliases are matched case-insensitively. Registering the same alias
+    /// twice replaces the previous mapping.
+    pub fn register(&mut self, alias: &str, freq: ChangeFreq) {
+        let _ = self.aliases.insert(alias.to_lowercase(), freq);
+    }
+
+    /// Parses a change frequency, falling back to the registered aliases.
+    ///
+    /// # Arguments
+    /// * `s` - The change frequency or alias to parse.
+    ///
+    /// # Returns
+    /// The matching `ChangeFreq`, or `SitemapError::InvalidChangeFreq` if the
+    /// value is neither a standard change frequency nor a registered alias.
+    pub fn parse(&self, s: &str) -> SitemapResult<ChangeFreq> {
+        s.parse().or_else(|err| {
+            self.aliases
+                .get(s.to_lowercase().as_str())
+                .copied()
+                .ok_or(err)
+        })
+    }
+}
+
 impl fmt::Display for ChangeFreq {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let s = match self {
-            ChangeFreq::Always => "always",
-            ChangeFreq::Hourly => "hourly",
-            ChangeFreq::Daily => "daily",
-            ChangeFreq::Weekly => "weekly",
-            ChangeFreq::Monthly => "monthly",
-            ChangeFreq::Yearly => "yearly",
-            ChangeFreq::Never => "never",
-        };
-        write!(f, "{}", s)
+        f.write_str(self.as_str())
     }
 }
 
@@ -124,6 +450,11 @@ pub fn create_site_map_data(
         changefreq,
         lastmod,
         loc,
+        priority: None,
+        extra: Vec::new(),
+        images: Vec::new(),
+        videos: Vec::new(),
+        alternates: Vec::new(),
     })
 }
 
@@ -175,20 +506,468 @@ pub fn convert_date_format(input: &str) -> String {
     input.to_string()
 }
 
+/// Controls how precisely `<lastmod>` values are written.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LastmodPrecision {
+    /// Datetime values are truncated to their `YYYY-MM-DD` date.
+    #[default]
+    DateOnly,
+    /// Values are written exactly as stored, including any time component.
+    Full,
+}
+
+/// A custom function rendering `<changefreq>` element text.
+#[derive(Clone)]
+struct ChangeFreqRenderer(
+    Arc<dyn Fn(ChangeFreq) -> String + Send + Sync>,
+);
+
+impl fmt::Debug for ChangeFreqRenderer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ChangeFreqRenderer(..)")
+    }
+}
+
+/// Options controlling how [`Sitemap::from_xml_with`] parses a document.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Rejects values that lenient parsing would tidy up, such as a `<loc>`
+    /// with surrounding whitespace.
+    pub strict: bool,
+    /// Rewrites each `lastmod` with [`convert_date_format`]. By default the
+    /// original value is kept verbatim.
+    pub normalize_dates: bool,
+}
+
+/// Per-entry limits on extension elements, checked by
+/// [`Sitemap::validate`].
+///
+/// The defaults are the limits search engines document. Lowering them
+/// flags entries early; serialization always enforces the documented
+/// limits.
+#[cfg(feature = "image-sitemap")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExtensionLimits {
+    /// The most images an entry may hold.
+    pub max_images: usize,
+}
+
+#[cfg(feature = "image-sitemap")]
+impl Default for ExtensionLimits {
+    fn default() -> Self {
+        ExtensionLimits {
+            max_images: MAX_IMAGES_PER_URL,
+        }
+    }
+}
+
+/// Formatting options for [`Sitemap::to_xml_with`].
+///
+/// The default indents nested elements by two spaces and ends lines with
+/// `\n`, as [`Sitemap::to_xml_pretty`] does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct XmlConfig {
+    /// The number of spaces per indentation level.
+    pub indent_width: usize,
+    /// The line ending written between elements, such as `\n` or `\r\n`.
+    pub line_ending: String,
+}
+
+impl Default for XmlConfig {
+    fn default() -> Self {
+        XmlConfig {
+            indent_width: 2,
+            line_ending: "\n".to_string(),
+        }
+    }
+}
+
+impl XmlConfig {
+    /// Returns the indenting emitter configuration for these options.
+    fn emitter_config(&self) -> EmitterConfig {
+        EmitterConfig::new()
+            .perform_indent(true)
+            .indent_string(" ".repeat(self.indent_width))
+            .line_separator(self.line_ending.clone())
+    }
+}
+
+/// The character encodings a sitemap can be serialized in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputEncoding {
+    Utf8,
+    Latin1,
+}
+
+impl OutputEncoding {
+    /// Looks up an encoding by label, ignoring case.
+    fn from_label(label: &str) -> Option<Self> {
+        match label.to_ascii_lowercase().as_str() {
+            "utf-8" | "utf8" => Some(OutputEncoding::Utf8),
+            "iso-8859-1" | "iso8859-1" | "latin1" | "latin-1" => {
+                Some(OutputEncoding::Latin1)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the name written in the XML declaration.
+    fn name(self) -> &'static str {
+        match self {
+            OutputEncoding::Utf8 => "UTF-8",
+            OutputEncoding::Latin1 => "ISO-8859-1",
+        }
+    }
+
+    /// Encodes `xml`, failing on the first character that doesn't fit.
+    fn encode(self, xml: String) -> SitemapResult<Vec<u8>> {
+        match self {
+            OutputEncoding::Utf8 => Ok(xml.into_bytes()),
+            OutputEncoding::Latin1 => xml
+                .chars()
+                .map(|character| {
+                    u8::try_from(u32::from(character)).map_err(|_| {
+                        SitemapError::UnencodableCharacter {
+                            character,
+                            encoding: self.name(),
+                        }
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
 /// Represents a complete sitemap.
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Clone)]
 pub struct Sitemap {
     entries: Vec<SiteMapData>,
+    default_changefreq: ChangeFreq,
+    extension_changefreqs: HashMap<String, ChangeFreq>,
+    extension_priorities: HashMap<String, f32>,
+    lastmod_precision: LastmodPrecision,
+    changefreq_renderer: Option<ChangeFreqRenderer>,
+    suppressed_changefreqs: Vec<ChangeFreq>,
+    cdata_loc: bool,
+    strict_lastmod: bool,
+    indent_size: Option<usize>,
+    encoding: OutputEncoding,
+    document_lastmod: bool,
+    section_depth: Option<usize>,
+    seen: Option<HashSet<Url>>,
+    omit_default_priority: bool,
+    #[cfg(feature = "image-sitemap")]
+    extension_limits: ExtensionLimits,
+}
+
+impl Default for Sitemap {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Sitemap {
     /// Creates a new empty `Sitemap`.
+    ///
+    /// URLs added with `add_url` that point to documents and images (such as
+    /// `.pdf` or `.jpg` files) default to a `Monthly` change frequency and
+    /// a priority of 0.3.
     pub fn new() -> Self {
         Sitemap {
             entries: Vec::new(),
+            default_changefreq: ChangeFreq::Weekly,
+            extension_changefreqs: DOCUMENT_EXTENSIONS
+                .iter()
+                .map(|ext| (ext.to_string(), ChangeFreq::Monthly))
+                .collect(),
+            extension_priorities: DOCUMENT_EXTENSIONS
+                .iter()
+                .map(|ext| (ext.to_string(), DOCUMENT_PRIORITY))
+                .collect(),
+            lastmod_precision: LastmodPrecision::DateOnly,
+            changefreq_renderer: None,
+            suppressed_changefreqs: Vec::new(),
+            cdata_loc: false,
+            strict_lastmod: false,
+            indent_size: None,
+            encoding: OutputEncoding::Utf8,
+            document_lastmod: false,
+            section_depth: None,
+            seen: None,
+            omit_default_priority: true,
+            #[cfg(feature = "image-sitemap")]
+            extension_limits: ExtensionLimits::default(),
         }
     }
 
+    /// Creates a new empty `Sitemap` with space for `capacity` entries.
+    ///
+    /// The capacity is capped at the 50,000 URLs a sitemap may hold, so huge
+    /// values such as `usize::MAX` never over-allocate or panic.
+    ///
+    /// # Arguments
+    /// * `capacity` - The number of entries to pre-allocate space for.
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut sitemap = Sitemap::new();
+        sitemap.entries.reserve_exact(capacity.min(MAX_URLS));
+        sitemap
+    }
+
+    /// Reserves space for at least `additional` more entries.
+    ///
+    /// The reservation never grows the sitemap beyond the 50,000 URLs it may
+    /// hold, however large `additional` is.
+    ///
+    /// # Arguments
+    /// * `additional` - The number of additional entries to reserve space for.
+    pub fn reserve(&mut self, additional: usize) {
+        let room = MAX_URLS.saturating_sub(self.entries.len());
+        self.entries.reserve_exact(additional.min(room));
+    }
+
+    /// Returns the number of entries the sitemap can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.entries.capacity()
+    }
+
+    /// Sets the change frequency used for entries added with `add_url`.
+    ///
+    /// # Arguments
+    /// * `changefreq` - The change frequency applied to new `add_url` entries.
+    pub fn with_default_changefreq(
+        mut self,
+        changefreq: ChangeFreq,
+    ) -> Self {
+        self.default_changefreq = changefreq;
+        self
+    }
+
+    /// Sets the change frequency used by `add_url` for URLs ending in `extension`.
+    ///
+    /// This takes precedence over the default change frequency, and replaces
+    /// any built-in mapping for the same extension.
+    ///
+    /// # Arguments
+    /// * `extension` - The file extension, without the leading dot (e.g. `pdf`).
+    /// * `changefreq` - The change frequency applied to matching URLs.
+    pub fn with_extension_changefreq(
+        mut self,
+        extension: &str,
+        changefreq: ChangeFreq,
+    ) -> Self {
+        let _ = self.extension_changefreqs.insert(
+            extension.trim_start_matches('.').to_lowercase(),
+            changefreq,
+        );
+        self
+    }
+
+    /// Sets the priority used by `add_url` for URLs ending in `extension`.
+    ///
+    /// This replaces any built-in mapping for the same extension. URLs with
+    /// other extensions get no priority.
+    ///
+    /// # Arguments
+    /// * `extension` - The file extension, without the leading dot (e.g. `pdf`).
+    /// * `priority` - The priority applied to matching URLs.
+    ///
+    /// # Returns
+    /// The updated sitemap, or `SitemapError::InvalidPriority` if
+    /// `priority` is outside the 0.0 to 1.0 range.
+    pub fn with_extension_priority(
+        mut self,
+        extension: &str,
+        priority: f32,
+    ) -> SitemapResult<Self> {
+        let _ = self.extension_priorities.insert(
+            extension.trim_start_matches('.').to_lowercase(),
+            check_priority(priority)?,
+        );
+        Ok(self)
+    }
+
+    /// Sets how precisely `<lastmod>` values are written by `to_xml`.
+    ///
+    /// The default, `LastmodPrecision::DateOnly`, truncates datetime values
+    /// such as `2024-10-08T12:30:00Z` to `2024-10-08`. Use
+    /// `LastmodPrecision::Full` to keep the time component.
+    ///
+    /// # Arguments
+    /// * `precision` - The precision used when writing `<lastmod>`.
+    pub fn with_lastmod_precision(
+        mut self,
+        precision: LastmodPrecision,
+    ) -> Self {
+        self.lastmod_precision = precision;
+        self
+    }
+
+    /// Overrides how `<changefreq>` values are written.
+    ///
+    /// Some crawlers expect non-standard casing or values. By default each
+    /// value is written with [`ChangeFreq::as_str`].
+    ///
+    /// # Arguments
+    /// * `renderer` - A function returning the element text for a change frequency.
+    pub fn with_changefreq_renderer<F>(&mut self, renderer: F)
+    where
+        F: Fn(ChangeFreq) -> String + Send + Sync + 'static,
+    {
+        self.changefreq_renderer =
+            Some(ChangeFreqRenderer(Arc::new(renderer)));
+    }
+
+    /// Omits the `<changefreq>` element of entries with one of the given
+    /// change frequencies.
+    ///
+    /// Some consider `Always` and `Never` misleading, and `<changefreq>` is
+    /// optional in the protocol. Each call replaces the previously
+    /// suppressed frequencies.
+    ///
+    /// # Arguments
+    /// * `freqs` - The change frequencies to leave out of the output.
+    pub fn suppress_changefreq(&mut self, freqs: &[ChangeFreq]) {
+        self.suppressed_changefreqs = freqs.to_vec();
+    }
+
+    /// Sets whether `<loc>` values are wrapped in CDATA sections.
+    ///
+    /// Some legacy consumers fail on escaped ampersands and expect
+    /// `<![CDATA[...]]>` instead. `Url` percent-encodes `>`, so a location can
+    /// never end the section early.
+    ///
+    /// # Arguments
+    /// * `enabled` - Whether to wrap locations in CDATA.
+    pub fn with_cdata_loc(mut self, enabled: bool) -> Self {
+        self.cdata_loc = enabled;
+        self
+    }
+
+    /// Enables pretty-printed output, indenting nested elements by
+    /// `indent_size` spaces per level.
+    ///
+    /// # Arguments
+    /// * `indent_size` - The number of spaces per indentation level.
+    pub fn with_pretty_print(mut self, indent_size: usize) -> Self {
+        self.indent_size = Some(indent_size);
+        self
+    }
+
+    /// Sets the character encoding of the serialized sitemap.
+    ///
+    /// Besides the default `UTF-8`, `ISO-8859-1` (also accepted as `latin1`)
+    /// is supported for legacy consumers. The encoding is declared by
+    /// [`Sitemap::to_xml`], and [`Sitemap::to_xml_bytes`] transcodes the
+    /// output to it.
+    ///
+    /// # Arguments
+    /// * `encoding` - The encoding label, matched case-insensitively.
+    ///
+    /// # Returns
+    /// The updated sitemap, or an error if the encoding is not supported.
+    pub fn with_encoding(
+        mut self,
+        encoding: &str,
+    ) -> SitemapResult<Self> {
+        self.encoding = OutputEncoding::from_label(encoding)
+            .ok_or_else(|| {
+                SitemapError::CustomError(format!(
+                    "Unsupported encoding '{}'",
+                    encoding
+                ))
+            })?;
+        Ok(self)
+    }
+
+    /// Sets whether the newest entry `lastmod` is noted in a comment at the
+    /// top of the document, such as `<!-- lastmod: 2024-10-08 -->`.
+    ///
+    /// This is not part of the protocol, but consumers ignore comments, so
+    /// it's a harmless way to record when the content last changed.
+    ///
+    /// # Arguments
+    /// * `enabled` - Whether to write the comment.
+    pub fn with_document_lastmod(mut self, enabled: bool) -> Self {
+        self.document_lastmod = enabled;
+        self
+    }
+
+    /// Groups entries by section, writing a comment such as
+    /// `<!-- /blog -->` before the first entry of each.
+    ///
+    /// A section is the first `depth` segments of an entry's path. Entries
+    /// are written sorted by section and then by path, so each section
+    /// appears once; the stored order is left untouched. Like the
+    /// [`Sitemap::with_document_lastmod`] comment, this is only meant for
+    /// people reading the file.
+    ///
+    /// # Arguments
+    /// * `depth` - The number of path segments that name a section.
+    pub fn with_section_comments(mut self, depth: usize) -> Self {
+        self.section_depth = Some(depth);
+        self
+    }
+
+    /// Makes [`Sitemap::add_entry`] reject URLs that are already in the
+    /// sitemap with `SitemapError::DuplicateUrl`.
+    ///
+    /// The URLs are tracked in a set, so the check doesn't scan the
+    /// entries. Entries already in the sitemap are tracked too, but
+    /// duplicates among them are kept.
+    pub fn with_dedup(mut self) -> Self {
+        self.seen = Some(HashSet::new());
+        self.reindex();
+        self
+    }
+
+    /// Sets whether a priority equal to the protocol default of 0.5 is
+    /// left out of the output.
+    ///
+    /// Search engines treat a missing `<priority>` as 0.5, so writing it
+    /// explicitly only adds bytes. This is enabled by default.
+    ///
+    /// # Arguments
+    /// * `enabled` - Whether to omit `<priority>0.5</priority>`.
+    pub fn with_omit_default_priority(mut self, enabled: bool) -> Self {
+        self.omit_default_priority = enabled;
+        self
+    }
+
+    /// Sets the per-entry extension limits checked by
+    /// [`Sitemap::validate`].
+    ///
+    /// # Arguments
+    /// * `limits` - The limits to check entries against.
+    #[cfg(feature = "image-sitemap")]
+    pub fn with_extension_limits(
+        mut self,
+        limits: ExtensionLimits,
+    ) -> Self {
+        self.extension_limits = limits;
+        self
+    }
+
+    /// Sets whether serialization rejects invalid `lastmod` values.
+    ///
+    /// By default, `lastmod` strings are written as stored. In strict mode,
+    /// serializing fails on the first entry whose `lastmod` isn't a valid
+    /// date or datetime, naming the entry's index and URL.
+    ///
+    /// # Arguments
+    /// * `strict` - Whether to validate `lastmod` values while serializing.
+    pub fn with_strict_lastmod(mut self, strict: bool) -> Self {
+        self.strict_lastmod = strict;
+        self
+    }
+
+    /// Removes all extension-specific change frequencies and priorities,
+    /// including the built-in document and image defaults.
+    pub fn without_extension_defaults(mut self) -> Self {
+        self.extension_changefreqs.clear();
+        self.extension_priorities.clear();
+        self
+    }
+
     /// Entry count of the sitemap.
     pub fn entry_count(&self) -> usize {
         self.entries.len()
@@ -200,7 +979,8 @@ pub fn entry_count(&self) -> usize {
     /// * `entry` - The `SiteMapData` entry to add to the sitemap.
     ///
     /// # Returns
-    /// `Ok(())` if the entry was added successfully, or an error if the sitemap would exceed size limits.
+    /// `Ok(())` if the entry was added successfully, or an error if the sitemap would exceed size limits
+    /// or the entry's URL is longer than 2,048 characters.
     pub fn add_entry(
         &mut self,
         entry: SiteMapData,
@@ -210,18 +990,768 @@ pub fn add_entry(
                 self.entries.len(),
             ));
         }
+        let len = entry.loc.as_str().len();
+        if len > MAX_URL_LENGTH {
+            return Err(SitemapError::UrlTooLong {
+                url: entry.loc.to_string(),
+                len,
+            });
+        }
+        if let Some(seen) = &mut self.seen {
+            if !seen.insert(entry.loc.clone()) {
+                return Err(SitemapError::DuplicateUrl(
+                    entry.loc.to_string(),
+                ));
+            }
+        }
         self.entries.push(entry);
         Ok(())
     }
 
-    /// Returns the current number of entries in the sitemap.
-    pub fn len(&self) -> usize {
-        self.entries.len()
-    }
-
-    /// Checks if the sitemap is empty.
-    pub fn is_empty(&self) -> bool {
-        self.entries.is_empty()
+    /// Rebuilds the set of URLs tracked by [`Sitemap::with_dedup`] after
+    /// entries were removed or changed.
+    fn reindex(&mut self) {
+        if let Some(seen) = &mut self.seen {
+            seen.clear();
+            seen.extend(
+                self.entries.iter().map(|entry| entry.loc.clone()),
+            );
+        }
+    }
+
+    /// Appends an entry without checking the URL limit.
+    ///
+    /// This is for callers that have already checked the limit, for
+    /// example by splitting their URLs beforehand. Keeping the sitemap
+    /// within 50,000 entries is the caller's responsibility: the limit is
+    /// only asserted in debug builds, and an oversized sitemap fails to
+    /// serialize. Use [`Sitemap::add_entry`] for the checked version.
+    ///
+    /// # Arguments
+    /// * `entry` - The `SiteMapData` entry to add.
+    pub fn push(&mut self, entry: SiteMapData) {
+        debug_assert!(
+            self.entries.len() < MAX_URLS,
+            "Sitemap::push past the 50,000 URL limit"
+        );
+        if let Some(seen) = &mut self.seen {
+            let _ = seen.insert(entry.loc.clone());
+        }
+        self.entries.push(entry);
+    }
+
+    /// Adds an entry only if it passes every per-entry check.
+    ///
+    /// The entry is checked with [`SiteMapData::validate`] (scheme, URL length
+    /// and `lastmod`), then against the URLs already in the sitemap. Nothing
+    /// is added if any check fails.
+    ///
+    /// # Arguments
+    /// * `entry` - The `SiteMapData` entry to add.
+    ///
+    /// # Returns
+    /// `Ok(())` if the entry was added, or the `ValidationIssue` that prevented it.
+    pub fn append_entry_validated(
+        &mut self,
+        entry: SiteMapData,
+    ) -> Result<(), ValidationIssue> {
+        entry.validate()?;
+        if self
+            .entries
+            .iter()
+            .any(|existing| existing.loc == entry.loc)
+        {
+            return Err(ValidationIssue::Duplicate(
+                entry.loc.to_string(),
+            ));
+        }
+        self.add_entry(entry)
+            .map_err(|_| ValidationIssue::SitemapFull)
+    }
+
+    /// Adds a URL to the sitemap using default metadata.
+    ///
+    /// The entry's `lastmod` is set to today's date. Its `changefreq` comes from
+    /// the extension mapping when the URL points to a known resource type
+    /// (`Monthly` for documents and images by default), and otherwise from the
+    /// sitemap's default change frequency (`Weekly` unless changed with
+    /// `with_default_changefreq`). Likewise, its `priority` comes from the
+    /// extension mapping (0.3 for documents and images by default), and is
+    /// otherwise left unset.
+    ///
+    /// # Arguments
+    /// * `loc` - The URL to add to the sitemap.
+    ///
+    /// # Returns
+    /// `Ok(())` if the entry was added successfully, or an error if the sitemap would exceed size limits.
+    pub fn add_url(&mut self, loc: Url) -> SitemapResult<()> {
+        let lastmod = DateTime::new().format("[year]-[month]-[day]")?;
+        let extension = url_extension(&loc);
+        let changefreq = extension
+            .as_ref()
+            .and_then(|ext| self.extension_changefreqs.get(ext))
+            .copied()
+            .unwrap_or(self.default_changefreq);
+        let priority = extension
+            .and_then(|ext| self.extension_priorities.get(&ext))
+            .copied();
+        self.add_entry(SiteMapData {
+            changefreq,
+            lastmod,
+            loc,
+            priority,
+            extra: Vec::new(),
+            images: Vec::new(),
+            videos: Vec::new(),
+            alternates: Vec::new(),
+        })
+    }
+
+    /// Reads URLs from a buffered reader and adds them with default metadata.
+    ///
+    /// Lines are parsed with [`crate::utils::parse_urls_from_reader`] and each
+    /// URL is added with [`Sitemap::add_url`].
+    ///
+    /// # Arguments
+    /// * `reader` - The source to read URLs from, one URL per line.
+    ///
+    /// # Returns
+    /// The number of URLs added, or an error if reading fails, a URL is
+    /// invalid, or the sitemap would exceed size limits. No URLs are added if
+    /// parsing fails.
+    pub fn append_urls_from_reader<R: BufRead>(
+        &mut self,
+        reader: R,
+    ) -> SitemapResult<usize> {
+        let urls = crate::utils::parse_urls_from_reader(reader)?;
+        let count = urls.len();
+        for url in urls {
+            self.add_url(url)?;
+        }
+        Ok(count)
+    }
+
+    /// Reads every child sitemap of an index from local files and merges
+    /// their entries into this sitemap.
+    ///
+    /// Each child's file is found by joining the path of its `<loc>` URL to
+    /// `base_dir`, so `https://example.com/sitemaps/blog.xml` is read from
+    /// `base_dir/sitemaps/blog.xml`.
+    ///
+    /// # Arguments
+    /// * `index` - The parsed sitemap index.
+    /// * `base_dir` - The local directory corresponding to the site root.
+    ///
+    /// # Returns
+    /// The number of entries added, or an error if a child file is missing or
+    /// unreadable, a child sitemap fails to parse, or the merged sitemap would
+    /// exceed size limits.
+    pub fn append_sitemap_index_children(
+        &mut self,
+        index: &SitemapIndex,
+        base_dir: &Path,
+    ) -> SitemapResult<usize> {
+        let mut added = 0;
+        for child in index.entries() {
+            let path =
+                base_dir.join(child.loc.path().trim_start_matches('/'));
+            let xml = fs::read_to_string(&path).map_err(|e| {
+                SitemapError::IoError(std::io::Error::new(
+                    e.kind(),
+                    format!(
+                        "Failed to read child sitemap {} from {}: {}",
+                        child.loc,
+                        path.display(),
+                        e
+                    ),
+                ))
+            })?;
+            for entry in Sitemap::from_xml(&xml)? {
+                self.add_entry(entry)?;
+                added += 1;
+            }
+        }
+        Ok(added)
+    }
+
+    /// Returns the current number of entries in the sitemap.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Checks if the sitemap is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns an iterator over the entries, in insertion order.
+    pub fn iter(&self) -> std::slice::Iter<'_, SiteMapData> {
+        self.entries.iter()
+    }
+
+    /// Returns an iterator over the entry locations, in insertion order.
+    pub fn iter_urls(&self) -> impl Iterator<Item = &Url> {
+        self.entries.iter().map(|entry| &entry.loc)
+    }
+
+    /// Returns the entries with the given change frequency, in insertion order.
+    ///
+    /// # Arguments
+    /// * `freq` - The change frequency to filter by.
+    pub fn entries_by_changefreq(
+        &self,
+        freq: ChangeFreq,
+    ) -> impl Iterator<Item = &SiteMapData> {
+        self.entries
+            .iter()
+            .filter(move |entry| entry.changefreq == freq)
+    }
+
+    /// Gives each entry without a priority one derived from its change
+    /// frequency, so pages that change more often rank higher.
+    ///
+    /// | Change frequency | Priority |
+    /// |------------------|----------|
+    /// | `Always`         | 1.0      |
+    /// | `Hourly`         | 0.9      |
+    /// | `Daily`          | 0.8      |
+    /// | `Weekly`         | 0.6      |
+    /// | `Monthly`        | 0.4      |
+    /// | `Yearly`         | 0.2      |
+    /// | `Never`          | 0.1      |
+    ///
+    /// Entries that already have a priority keep it.
+    pub fn assign_priority_from_changefreq(&mut self) {
+        for entry in &mut self.entries {
+            if entry.priority.is_none() {
+                entry.priority = Some(match entry.changefreq {
+                    ChangeFreq::Always => 1.0,
+                    ChangeFreq::Hourly => 0.9,
+                    ChangeFreq::Daily => 0.8,
+                    ChangeFreq::Weekly => 0.6,
+                    ChangeFreq::Monthly => 0.4,
+                    ChangeFreq::Yearly => 0.2,
+                    ChangeFreq::Never => 0.1,
+                });
+            }
+        }
+    }
+
+    /// Returns the entries whose `lastmod` lies more than `tolerance` in the
+    /// future.
+    ///
+    /// Future dates are almost always a bug, such as a wrong timezone or a
+    /// date format mix-up, and some crawlers ignore them. Entries with an
+    /// unparseable `lastmod` are not included.
+    ///
+    /// # Arguments
+    /// * `tolerance` - How far ahead of now a `lastmod` may be, to allow for
+    ///   clock skew.
+    pub fn future_dated_entries(
+        &self,
+        tolerance: std::time::Duration,
+    ) -> Vec<&SiteMapData> {
+        let now = DateTime::new();
+        let tolerance =
+            i64::try_from(tolerance.as_secs()).unwrap_or(i64::MAX);
+        self.entries
+            .iter()
+            .filter(|entry| {
+                entry.lastmod_as_datetime().map_or(false, |lastmod| {
+                    lastmod.duration_since(&now).whole_seconds()
+                        > tolerance
+                })
+            })
+            .collect()
+    }
+
+    /// Counts the distinct paths of the sitemap's URLs, ignoring their
+    /// scheme, host, query and fragment.
+    ///
+    /// A count well below [`Sitemap::len`] points to pages listed several
+    /// times under different query parameters.
+    pub fn count_distinct_paths(&self) -> usize {
+        self.entries
+            .iter()
+            .map(|entry| entry.loc.path())
+            .collect::<HashSet<_>>()
+            .len()
+    }
+
+    /// Returns the distinct hosts of the sitemap's URLs, in insertion order.
+    ///
+    /// This is useful to confirm that a sitemap doesn't accidentally mix
+    /// domains. URLs without a host are not included.
+    pub fn hosts(&self) -> Vec<String> {
+        let mut seen = HashSet::new();
+        self.entries
+            .iter()
+            .filter_map(|entry| entry.loc.host_str())
+            .filter(|host| seen.insert(*host))
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Counts directory-like URLs with and without a trailing slash.
+    ///
+    /// A URL is directory-like when the last segment of its path has no file
+    /// extension. Root URLs are not counted, as they always end with a slash.
+    /// Non-zero counts on both sides mean the sitemap mixes conventions.
+    ///
+    /// # Returns
+    /// A `(with_slash, without_slash)` tuple of counts.
+    pub fn trailing_slash_report(&self) -> (usize, usize) {
+        let mut with_slash = 0;
+        let mut without_slash = 0;
+        for entry in &self.entries {
+            let path = entry.loc.path();
+            if path == "/" {
+                continue;
+            }
+            if path.ends_with('/') {
+                with_slash += 1;
+            } else if url_extension(&entry.loc).is_none() {
+                without_slash += 1;
+            }
+        }
+        (with_slash, without_slash)
+    }
+
+    /// Checks that every URL in the sitemap shares a single host.
+    ///
+    /// The first host seen is treated as the sitemap's host. URLs without a
+    /// host are ignored.
+    ///
+    /// # Returns
+    /// `Ok(())` if at most one host is present, or a `CustomError` listing the
+    /// extra hosts otherwise.
+    pub fn assert_single_host(&self) -> SitemapResult<()> {
+        let hosts = self.hosts();
+        match hosts.split_first() {
+            Some((host, extra)) if !extra.is_empty() => {
+                Err(SitemapError::CustomError(format!(
+                    "Sitemap for {} also contains other hosts: {}",
+                    host,
+                    extra.join(", ")
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that every URL in the sitemap uses `https`.
+    ///
+    /// This is meant for verifying an HTTPS migration: unlike the filtering
+    /// done during normalization, the sitemap is left untouched and every
+    /// offending URL is reported.
+    ///
+    /// # Returns
+    /// `Ok(())` if no URL uses `http`, or a `CustomError` listing the `http`
+    /// URLs otherwise.
+    pub fn assert_all_https(&self) -> SitemapResult<()> {
+        let insecure: Vec<&str> = self
+            .iter_urls()
+            .filter(|url| url.scheme() == "http")
+            .map(Url::as_str)
+            .collect();
+        if insecure.is_empty() {
+            Ok(())
+        } else {
+            Err(SitemapError::CustomError(format!(
+                "Sitemap contains {} http URL(s): {}",
+                insecure.len(),
+                insecure.join(", ")
+            )))
+        }
+    }
+
+    /// Removes every entry whose URL path starts with `prefix`.
+    ///
+    /// The match is a plain string prefix on the path, so `/drafts` also
+    /// removes `/drafts-archive`; pass `/drafts/` to only drop the
+    /// directory's contents.
+    ///
+    /// # Arguments
+    /// * `prefix` - The path prefix to remove, such as `/private/`.
+    ///
+    /// # Returns
+    /// The number of entries removed.
+    pub fn remove_by_prefix(&mut self, prefix: &str) -> usize {
+        let before = self.entries.len();
+        self.entries
+            .retain(|entry| !entry.loc.path().starts_with(prefix));
+        self.reindex();
+        before - self.entries.len()
+    }
+
+    /// Removes every entry located at `loc`, including duplicates.
+    ///
+    /// # Arguments
+    /// * `loc` - The URL of the entries to remove.
+    ///
+    /// # Returns
+    /// `true` if any entry was removed.
+    pub fn remove(&mut self, loc: &Url) -> bool {
+        let before = self.entries.len();
+        self.entries.retain(|entry| &entry.loc != loc);
+        self.reindex();
+        self.entries.len() != before
+    }
+
+    /// Keeps only the entries for which `f` returns `true`, preserving
+    /// their order.
+    ///
+    /// # Arguments
+    /// * `f` - The predicate deciding which entries to keep.
+    pub fn retain<F: FnMut(&SiteMapData) -> bool>(&mut self, f: F) {
+        self.entries.retain(f);
+        self.reindex();
+    }
+
+    /// Removes all entries, keeping the sitemap's settings.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.reindex();
+    }
+
+    /// Sorts the entries by location, comparing the URL strings.
+    pub fn sort_by_loc(&mut self) {
+        self.entries
+            .sort_by(|a, b| a.loc.as_str().cmp(b.loc.as_str()));
+    }
+
+    /// Sorts the entries by last modification date, oldest first.
+    ///
+    /// Entries whose `lastmod` can't be parsed come after the rest, ordered
+    /// by their `lastmod` string. The sort is stable.
+    pub fn sort_by_lastmod(&mut self) {
+        self.entries.sort_by(|a, b| {
+            match (a.lastmod_as_datetime(), b.lastmod_as_datetime()) {
+                (Some(a), Some(b)) => {
+                    a.duration_since(&b).whole_nanoseconds().cmp(&0)
+                }
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => a.lastmod.cmp(&b.lastmod),
+            }
+        });
+    }
+
+    /// Sorts the entries with a comparator function. The sort is stable.
+    ///
+    /// # Arguments
+    /// * `compare` - Returns the ordering of two entries.
+    pub fn sort_by<F>(&mut self, compare: F)
+    where
+        F: FnMut(&SiteMapData, &SiteMapData) -> Ordering,
+    {
+        self.entries.sort_by(compare);
+    }
+
+    /// Normalizes the location of every entry in place, as the CLI does
+    /// for its input URLs.
+    ///
+    /// Each location is rewritten with
+    /// [`crate::utils::normalize_url_with`]. Entries that don't use the
+    /// http or https scheme are dropped, as are entries whose normalized
+    /// location duplicates an earlier one.
+    ///
+    /// # Arguments
+    /// * `options` - The normalization options.
+    ///
+    /// # Returns
+    /// The number of entries removed.
+    pub fn normalize_all(
+        &mut self,
+        options: &crate::utils::NormalizeOptions,
+    ) -> usize {
+        let before = self.entries.len();
+        let mut seen = HashSet::new();
+        let entries = std::mem::take(&mut self.entries);
+        for mut entry in entries {
+            let loc = match crate::utils::normalize_url_with(
+                entry.loc, options,
+            ) {
+                Some(loc) => loc,
+                None => continue,
+            };
+            if seen.insert(loc.clone()) {
+                entry.loc = loc;
+                self.entries.push(entry);
+            }
+        }
+        self.reindex();
+        before - self.entries.len()
+    }
+
+    /// Combines several sitemaps into one, dropping duplicate URLs.
+    ///
+    /// Entries keep their order, with the first occurrence of a URL winning.
+    /// The result uses the serialization settings of the first sitemap.
+    ///
+    /// # Arguments
+    /// * `sitemaps` - The sitemaps to combine.
+    ///
+    /// # Returns
+    /// The combined sitemap, or an error reporting the total number of
+    /// unique URLs if it exceeds the 50,000 URL limit.
+    pub fn merge_all(
+        sitemaps: impl IntoIterator<Item = Sitemap>,
+    ) -> SitemapResult<Sitemap> {
+        let mut merged: Option<Sitemap> = None;
+        let mut seen = HashSet::new();
+        let mut entries = Vec::new();
+        for sitemap in sitemaps {
+            if merged.is_none() {
+                merged = Some(sitemap.empty_copy());
+            }
+            for entry in sitemap.entries {
+                if seen.insert(entry.loc.clone()) {
+                    entries.push(entry);
+                }
+            }
+        }
+
+        if entries.len() > MAX_URLS {
+            return Err(SitemapError::MaxUrlLimitExceeded(
+                entries.len(),
+            ));
+        }
+        let mut merged = merged.unwrap_or_default();
+        merged.entries = entries;
+        merged.reindex();
+        Ok(merged)
+    }
+
+    /// Lists pairs of URLs that differ only by the case of their path or
+    /// query.
+    ///
+    /// Hosts are case-insensitive, but paths are not, so `/About` and
+    /// `/about` are distinct URLs that often serve the same content. Each URL
+    /// is paired with the first URL seen that collides with it.
+    ///
+    /// # Returns
+    /// The colliding pairs, in the order the second URL of each pair appears.
+    pub fn case_collision_report(&self) -> Vec<(Url, Url)> {
+        let mut first_seen: HashMap<String, &Url> = HashMap::new();
+        let mut collisions = Vec::new();
+        for entry in &self.entries {
+            let key = entry.loc.as_str().to_lowercase();
+            match first_seen.get(&key) {
+                Some(first) if **first != entry.loc => {
+                    collisions
+                        .push(((*first).clone(), entry.loc.clone()));
+                }
+                Some(_) => {}
+                None => {
+                    let _ = first_seen.insert(key, &entry.loc);
+                }
+            }
+        }
+        collisions
+    }
+
+    /// Carries `lastmod` values forward from a previously generated sitemap.
+    ///
+    /// For every entry whose URL also appears in `previous`, the previous
+    /// `lastmod` replaces the current one, so regenerating a sitemap only
+    /// bumps the dates of URLs that are new.
+    ///
+    /// # Arguments
+    /// * `previous` - The sitemap generated by the previous run.
+    pub fn merge_preserving_lastmod(&mut self, previous: &Sitemap) {
+        let previous_lastmods: HashMap<&str, &str> = previous
+            .entries
+            .iter()
+            .map(|entry| (entry.loc.as_str(), entry.lastmod.as_str()))
+            .collect();
+        for entry in &mut self.entries {
+            if let Some(lastmod) =
+                previous_lastmods.get(entry.loc.as_str())
+            {
+                entry.lastmod = lastmod.to_string();
+            }
+        }
+    }
+
+    /// Splits the sitemap into consecutive chunks of at most `max_per_file`
+    /// entries.
+    ///
+    /// Entries keep their order, and every chunk keeps this sitemap's
+    /// serialization settings, so each can be written with
+    /// [`Sitemap::to_xml`] on its own.
+    ///
+    /// # Arguments
+    /// * `max_per_file` - The maximum number of entries per chunk. Values
+    ///   are clamped to between 1 and 50,000.
+    ///
+    /// # Returns
+    /// The chunks, or an empty vector if the sitemap is empty.
+    pub fn split(&self, max_per_file: usize) -> Vec<Sitemap> {
+        self.entries
+            .chunks(max_per_file.clamp(1, MAX_URLS))
+            .map(|chunk| {
+                let mut sitemap = self.empty_copy();
+                sitemap.entries = chunk.to_vec();
+                sitemap.reindex();
+                sitemap
+            })
+            .collect()
+    }
+
+    /// Splits the sitemap into several sitemaps grouped by a key function.
+    ///
+    /// Each entry goes into the sitemap for the key `f` returns for it, so
+    /// sitemaps can be sharded by any criteria, such as content type or the
+    /// first letter of the path. Every resulting sitemap keeps this sitemap's
+    /// serialization settings and the entries' relative order.
+    ///
+    /// # Arguments
+    /// * `f` - A function returning the shard key for an entry.
+    pub fn split_by_key<K, F>(&self, f: F) -> HashMap<K, Sitemap>
+    where
+        K: Eq + Hash,
+        F: Fn(&SiteMapData) -> K,
+    {
+        let mut shards: HashMap<K, Sitemap> = HashMap::new();
+        for entry in &self.entries {
+            shards
+                .entry(f(entry))
+                .or_insert_with(|| self.empty_copy())
+                .push(entry.clone());
+        }
+        shards
+    }
+
+    /// Splits the sitemap into one sitemap per language, using the
+    /// entries' alternates.
+    ///
+    /// An entry's language is the `hreflang` of the alternate pointing back
+    /// at the entry itself, or of its first alternate if none does. Entries
+    /// without alternates are grouped under `x-default`. Like
+    /// [`Sitemap::split_by_key`], every resulting sitemap keeps this
+    /// sitemap's settings and the entries' relative order.
+    ///
+    /// # Returns
+    /// The sitemaps, keyed by language code.
+    pub fn split_by_language(&self) -> HashMap<String, Sitemap> {
+        self.split_by_key(|entry| {
+            entry
+                .alternates
+                .iter()
+                .find(|alternate| alternate.href == entry.loc)
+                .or_else(|| entry.alternates.first())
+                .map_or_else(
+                    || "x-default".to_string(),
+                    |alternate| alternate.hreflang.clone(),
+                )
+        })
+    }
+
+    /// Returns an empty sitemap with the same settings as this one.
+    fn empty_copy(&self) -> Sitemap {
+        Sitemap {
+            entries: Vec::new(),
+            default_changefreq: self.default_changefreq,
+            extension_changefreqs: self.extension_changefreqs.clone(),
+            extension_priorities: self.extension_priorities.clone(),
+            lastmod_precision: self.lastmod_precision,
+            changefreq_renderer: self.changefreq_renderer.clone(),
+            suppressed_changefreqs: self.suppressed_changefreqs.clone(),
+            cdata_loc: self.cdata_loc,
+            strict_lastmod: self.strict_lastmod,
+            indent_size: self.indent_size,
+            encoding: self.encoding,
+            document_lastmod: self.document_lastmod,
+            section_depth: self.section_depth,
+            seen: self.seen.as_ref().map(|_| HashSet::new()),
+            omit_default_priority: self.omit_default_priority,
+            #[cfg(feature = "image-sitemap")]
+            extension_limits: self.extension_limits,
+        }
+    }
+
+    /// Validates every entry against the protocol rules for a sitemap
+    /// published at `location`.
+    ///
+    /// Besides the per-entry checks of [`SiteMapData::validate`], each URL
+    /// must share the scheme and host of `location` and sit under its
+    /// directory: a sitemap at `https://example.com/sub/sitemap.xml` may
+    /// only list URLs under `https://example.com/sub/`. Every URL is out of
+    /// scope of a `location` that can't have a directory, such as a
+    /// `mailto:` URL. With the
+    /// `image-sitemap` feature, entries must also stay within the sitemap's
+    /// `ExtensionLimits`.
+    ///
+    /// # Arguments
+    /// * `location` - The URL the sitemap will be published at.
+    ///
+    /// # Returns
+    /// The issues found, in entry order; empty if the sitemap is valid.
+    pub fn validate(&self, location: &Url) -> Vec<ValidationIssue> {
+        let directory = if location.cannot_be_a_base() {
+            None
+        } else {
+            let path = location.path();
+            let mut directory = location.clone();
+            directory.set_path(match path.rfind('/') {
+                Some(end) => &path[..=end],
+                None => "/",
+            });
+            directory.set_query(None);
+            directory.set_fragment(None);
+            Some(directory)
+        };
+
+        let mut issues = Vec::new();
+        #[cfg_attr(
+            not(feature = "image-sitemap"),
+            allow(unused_variables)
+        )]
+        for (index, entry) in self.entries.iter().enumerate() {
+            if let Err(issue) = entry.validate() {
+                issues.push(issue);
+            }
+            #[cfg(feature = "image-sitemap")]
+            if entry.images.len() > self.extension_limits.max_images {
+                issues.push(ValidationIssue::TooManyImages {
+                    index,
+                    count: entry.images.len(),
+                });
+            }
+            let in_scope =
+                directory.as_ref().map_or(false, |directory| {
+                    entry.loc.as_str().starts_with(directory.as_str())
+                });
+            if !in_scope {
+                issues.push(ValidationIssue::OutOfScope(
+                    entry.loc.to_string(),
+                ));
+            }
+        }
+        issues
+    }
+
+    /// Splits the entries into those that pass validation and those that don't.
+    ///
+    /// The sitemap itself is left untouched, so the valid entries can be
+    /// exported while the invalid ones are reported.
+    ///
+    /// # Returns
+    /// A tuple of the valid entries and the invalid entries paired with the
+    /// `ValidationIssue` that disqualified them, both in insertion order.
+    pub fn partition_valid(
+        &self,
+    ) -> (Vec<SiteMapData>, Vec<(SiteMapData, ValidationIssue)>) {
+        let mut valid = Vec::new();
+        let mut invalid = Vec::new();
+        for entry in &self.entries {
+            match entry.validate() {
+                Ok(()) => valid.push(entry.clone()),
+                Err(issue) => invalid.push((entry.clone(), issue)),
+            }
+        }
+        (valid, invalid)
     }
 
     /// Generates the XML representation of the sitemap.
@@ -230,133 +1760,3127 @@ pub fn is_empty(&self) -> bool {
     /// A string containing the XML representation of the sitemap, or an error if generation fails.
     pub fn to_xml(&self) -> SitemapResult<String> {
         // Pre-allocate enough space in the Vec to avoid reallocations.
-        let estimated_size = self.entries.len() * 300; // Rough estimate of average entry size in bytes
-        let mut output = Vec::with_capacity(estimated_size);
-        let mut writer = EventWriter::new(&mut output);
-
-        writer.write(XmlEvent::StartDocument {
-            version: xml::common::XmlVersion::Version10,
-            encoding: Some("UTF-8"),
-            standalone: None,
-        })?;
+        let mut output = Vec::with_capacity(estimated_size(self.len()));
+        self.write_utf8_to(&mut output)?;
+        into_checked_xml(output)
+    }
+
+    /// Generates the XML representation of the sitemap into `buf`, reusing
+    /// its allocation.
+    ///
+    /// The buffer is cleared first, so regenerating a sitemap in a loop
+    /// doesn't allocate a new string each time. The output is the same as
+    /// [`Sitemap::to_xml`]. On error, `buf` is left empty.
+    ///
+    /// # Arguments
+    /// * `buf` - The buffer to write the XML to.
+    ///
+    /// # Returns
+    /// `Ok(())` if the XML was written, or an error if generation fails.
+    pub fn serialize_into(
+        &self,
+        buf: &mut String,
+    ) -> SitemapResult<()> {
+        let mut output = std::mem::take(buf).into_bytes();
+        output.clear();
+        output.reserve(estimated_size(self.len()));
+        self.write_utf8_to(&mut output)?;
+        *buf = into_checked_xml(output)?;
+        Ok(())
+    }
+
+    /// Generates an indented XML representation of the sitemap, using the
+    /// default [`XmlConfig`].
+    ///
+    /// # Returns
+    /// The indented XML, or an error if generation fails.
+    pub fn to_xml_pretty(&self) -> SitemapResult<String> {
+        self.to_xml_with(&XmlConfig::default())
+    }
+
+    /// Generates an indented XML representation of the sitemap.
+    ///
+    /// This overrides any [`Sitemap::with_pretty_print`] setting, and
+    /// [`Sitemap::to_xml`] stays compact unless that is set. The 10MB
+    /// limit applies to the indented output.
+    ///
+    /// # Arguments
+    /// * `config` - The indentation width and line ending to use.
+    ///
+    /// # Returns
+    /// The indented XML, or an error if generation fails.
+    pub fn to_xml_with(
+        &self,
+        config: &XmlConfig,
+    ) -> SitemapResult<String> {
+        let mut output = Vec::with_capacity(estimated_size(self.len()));
+        let mut writer =
+            config.emitter_config().create_writer(&mut output);
+        self.write_document(&mut writer)?;
+        into_checked_xml(output)
+    }
+
+    /// Writes the XML representation of the sitemap to `writer`.
+    ///
+    /// Events are streamed straight into `writer`, so a large sitemap can be
+    /// written to a `File` or a compressor without building the document in
+    /// memory. Unlike [`Sitemap::to_xml`], this does not enforce the 10MB
+    /// `MAX_SITEMAP_SIZE` limit. A sitemap with a non-UTF-8 encoding is
+    /// still built in memory to be transcoded.
+    ///
+    /// # Arguments
+    /// * `writer` - The destination for the XML.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or an error if generation or writing fails. On
+    /// error, `writer` may hold a partial document.
+    pub fn write_to<W: Write>(
+        &self,
+        mut writer: W,
+    ) -> SitemapResult<()> {
+        if self.encoding != OutputEncoding::Utf8 {
+            writer.write_all(&self.to_xml_bytes()?)?;
+            writer.flush()?;
+            return Ok(());
+        }
+        self.write_utf8_to(writer)
+    }
+
+    /// Streams the UTF-8 document into `writer` and flushes it.
+    fn write_utf8_to<W: Write>(&self, writer: W) -> SitemapResult<()> {
+        let mut writer = self.emitter_config().create_writer(writer);
+        self.write_document(&mut writer)?;
+        writer.into_inner().flush()?;
+        Ok(())
+    }
+
+    /// Generates the XML representation of the sitemap in its encoding.
+    ///
+    /// For the default `UTF-8` encoding this is the output of
+    /// [`Sitemap::to_xml`] as bytes; otherwise the document is transcoded to
+    /// the encoding set with [`Sitemap::with_encoding`].
+    ///
+    /// # Returns
+    /// The encoded document, or an error if generation fails or the sitemap
+    /// contains a character the encoding cannot represent.
+    pub fn to_xml_bytes(&self) -> SitemapResult<Vec<u8>> {
+        self.encoding.encode(self.to_xml()?)
+    }
+
+    /// Writes the gzip-compressed XML representation of the sitemap to `writer`.
+    ///
+    /// The XML is compressed as it is generated, without building the whole
+    /// document in memory first. The uncompressed size is still checked
+    /// against the 10MB limit, but only once everything has been written, so
+    /// on error `writer` may hold a partial stream.
+    ///
+    /// Requires the `gzip` feature.
+    ///
+    /// # Arguments
+    /// * `writer` - The destination for the compressed bytes.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or an error if writing fails or the uncompressed
+    /// sitemap exceeds the size limit.
+    #[cfg(feature = "gzip")]
+    pub fn write_gzip_to<W: Write>(
+        &self,
+        writer: W,
+    ) -> SitemapResult<()> {
+        let mut encoder = flate2::write::GzEncoder::new(
+            writer,
+            flate2::Compression::default(),
+        );
+        if self.encoding != OutputEncoding::Utf8 {
+            encoder.write_all(&self.to_xml_bytes()?)?;
+            let _ = encoder.finish()?;
+            return Ok(());
+        }
+        let mut writer = self
+            .emitter_config()
+            .create_writer(ByteCounter::new(encoder));
+        self.write_document(&mut writer)?;
+
+        let counter = writer.into_inner();
+        let size = counter.count;
+        let _ = counter.inner.finish()?;
+        if size > MAX_SITEMAP_SIZE {
+            return Err(SitemapError::SitemapTooLarge {
+                size,
+                limit: MAX_SITEMAP_SIZE,
+            });
+        }
+        Ok(())
+    }
+
+    /// Returns the emitter configuration for the sitemap's output style.
+    fn emitter_config(&self) -> EmitterConfig {
+        match self.indent_size {
+            Some(indent_width) => XmlConfig {
+                indent_width,
+                ..XmlConfig::default()
+            }
+            .emitter_config(),
+            None => EmitterConfig::new(),
+        }
+    }
+
+    /// Writes the complete `<urlset>` document.
+    fn write_document<W: Write>(
+        &self,
+        writer: &mut EventWriter<W>,
+    ) -> SitemapResult<()> {
+        // `push` skips the limit check, so it's enforced here as well.
+        if self.entries.len() > MAX_URLS {
+            return Err(SitemapError::MaxUrlLimitExceeded(
+                self.entries.len(),
+            ));
+        }
+        let namespaces = extension_namespaces(
+            self.entries.iter().map(|entry| entry.extensions()),
+        )?;
+        write_urlset_start(writer, self.encoding.name(), &namespaces)?;
+        if self.document_lastmod {
+            if let Some(lastmod) = self.newest_lastmod() {
+                writer.write(XmlEvent::comment(&format!(
+                    " lastmod: {} ",
+                    lastmod_text(lastmod, self.lastmod_precision)
+                )))?;
+            }
+        }
+        match self.section_depth {
+            Some(depth) => self.write_sections(writer, depth)?,
+            None => {
+                for (index, entry) in self.entries.iter().enumerate() {
+                    self.write_entry(writer, index, entry)?;
+                }
+            }
+        }
+        // Close the <urlset> element
+        writer.write(XmlEvent::end_element())?;
+        Ok(())
+    }
+
+    /// Writes the entries sorted by section, each section preceded by a
+    /// comment naming it.
+    fn write_sections<W: Write>(
+        &self,
+        writer: &mut EventWriter<W>,
+        depth: usize,
+    ) -> SitemapResult<()> {
+        let mut entries: Vec<(String, usize, &SiteMapData)> = self
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                (section_of(&entry.loc, depth), index, entry)
+            })
+            .collect();
+        entries.sort_by(|a, b| {
+            (&a.0, a.2.loc.path()).cmp(&(&b.0, b.2.loc.path()))
+        });
+        let mut current = None;
+        for (section, index, entry) in &entries {
+            if current != Some(section) {
+                // `--` may not appear inside a comment.
+                writer.write(XmlEvent::comment(&format!(
+                    " {} ",
+                    section.replace("--", "-%2D")
+                )))?;
+                current = Some(section);
+            }
+            self.write_entry(writer, *index, entry)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the most recent valid `lastmod` among the entries.
+    fn newest_lastmod(&self) -> Option<&str> {
+        self.entries
+            .iter()
+            .filter_map(|entry| {
+                entry
+                    .lastmod_as_datetime()
+                    .map(|date| (date, entry.lastmod.as_str()))
+            })
+            // `DateTime`'s `Ord` ignores offsets, so compare instants.
+            .max_by(|a, b| {
+                a.0.duration_since(&b.0).whole_nanoseconds().cmp(&0)
+            })
+            .map(|(_, lastmod)| lastmod)
+    }
+
+    /// Writes a single `<url>` element using the sitemap's settings.
+    fn write_entry<W: Write>(
+        &self,
+        writer: &mut EventWriter<W>,
+        index: usize,
+        entry: &SiteMapData,
+    ) -> SitemapResult<()> {
+        if self.strict_lastmod && entry.lastmod_as_datetime().is_none()
+        {
+            return Err(SitemapError::InvalidLastmod {
+                index,
+                url: entry.loc.to_string(),
+                lastmod: entry.lastmod.clone(),
+            });
+        }
+        let changefreq = match &self.changefreq_renderer {
+            _ if self
+                .suppressed_changefreqs
+                .contains(&entry.changefreq) =>
+            {
+                None
+            }
+            Some(ChangeFreqRenderer(render)) => {
+                Some(Cow::Owned(render(entry.changefreq)))
+            }
+            None => Some(Cow::Borrowed(entry.changefreq.as_str())),
+        };
+        let priority =
+            entry.priority.map(check_priority).transpose()?.filter(
+                |&priority| {
+                    !(self.omit_default_priority
+                        && priority == DEFAULT_PRIORITY)
+                },
+            );
+        write_url(
+            writer,
+            entry.loc.as_str(),
+            &lastmod_text(&entry.lastmod, self.lastmod_precision),
+            changefreq.as_deref(),
+            priority,
+            entry.extensions(),
+            false,
+            self.cdata_loc,
+        )
+    }
+
+    /// Returns a hex SHA-256 checksum of the sitemap's canonical XML.
+    ///
+    /// Entries are sorted by location, last modification date and change
+    /// frequency before serializing, so two sitemaps holding the same entries
+    /// in a different order produce the same checksum. This makes it suitable
+    /// for detecting whether a regenerated sitemap differs from a deployed one.
+    ///
+    /// # Returns
+    /// The lowercase hex digest, or an error if serialization fails.
+    pub fn checksum(&self) -> SitemapResult<String> {
+        let mut canonical = self.clone();
+        canonical.entries.sort_by(|a, b| {
+            (a.loc.as_str(), &a.lastmod, a.changefreq.as_str()).cmp(&(
+                b.loc.as_str(),
+                &b.lastmod,
+                b.changefreq.as_str(),
+            ))
+        });
+        Ok(sha256::hex_digest(canonical.to_xml()?.as_bytes()))
+    }
+
+    /// Generates the gzip-compressed XML representation of the sitemap.
+    ///
+    /// The 10MB limit applies to the uncompressed XML, as the protocol
+    /// requires. Use [`crate::utils::write_output_gz`] to save the result.
+    ///
+    /// Requires the `gzip` feature.
+    ///
+    /// # Returns
+    /// The compressed bytes, or an error if serialization or compression fails.
+    #[cfg(feature = "gzip")]
+    #[doc(alias = "to_xml_gz")]
+    pub fn to_xml_gzip(&self) -> SitemapResult<Vec<u8>> {
+        gzip(&self.to_xml_bytes()?, flate2::Compression::default())
+    }
+
+    /// Estimates the size of the output of [`Sitemap::to_xml_gzip`].
+    ///
+    /// The sitemap is compressed with the fastest gzip level, which is
+    /// cheaper than the default level and typically produces slightly larger
+    /// output, so the estimate errs on the high side.
+    ///
+    /// Requires the `gzip` feature.
+    ///
+    /// # Returns
+    /// The estimated compressed size in bytes, or an error if serialization fails.
+    #[cfg(feature = "gzip")]
+    pub fn compress_estimate(&self) -> SitemapResult<usize> {
+        gzip(&self.to_xml_bytes()?, flate2::Compression::fast())
+            .map(|compressed| compressed.len())
+    }
+
+    /// Generates a JSON array describing the sitemap's entries.
+    ///
+    /// Each entry becomes an object with `loc`, `lastmod`, `changefreq` and
+    /// `priority` fields, where `priority` is `null` if unset. Extension
+    /// elements such as images are not included.
+    ///
+    /// Requires the `json` feature.
+    ///
+    /// # Returns
+    /// The JSON text.
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> SitemapResult<String> {
+        let entries = self
+            .entries
+            .iter()
+            .map(|entry| {
+                serde_json::json!({
+                    "loc": entry.loc.as_str(),
+                    "lastmod": lastmod_text(
+                        &entry.lastmod,
+                        self.lastmod_precision
+                    ),
+                    "changefreq": entry.changefreq.as_str(),
+                    // Go through the text form so 0.8 isn't widened to
+                    // 0.800000011920929.
+                    "priority": entry.priority.and_then(|priority| {
+                        priority_text(priority).parse::<f64>().ok()
+                    }),
+                })
+            })
+            .collect();
+        Ok(serde_json::Value::Array(entries).to_string())
+    }
+
+    /// Parses a sitemap from its XML representation.
+    ///
+    /// Only the `<loc>`, `<lastmod>`, `<changefreq>` and `<priority>`
+    /// children of each `<url>` element are read. Other children, such as
+    /// extension blocks like `<image:image>`, are skipped.
+    ///
+    /// Parsing is lenient: whitespace around `<loc>` values is trimmed, and
+    /// a malformed or out-of-range `<priority>` is dropped with a warning.
+    /// Use [`Sitemap::from_xml_with`] for strict parsing.
+    ///
+    /// # Arguments
+    /// * `xml` - The XML document to parse.
+    ///
+    /// # Returns
+    /// The parsed `Sitemap`, or an error if the document is malformed, an entry has no
+    /// `<loc>`, a value is invalid, or the sitemap would exceed size limits.
+    pub fn from_xml(xml: &str) -> SitemapResult<Sitemap> {
+        Self::from_xml_with(xml, ParseOptions::default())
+    }
+
+    /// Parses a sitemap from its XML representation with the given options.
+    ///
+    /// # Arguments
+    /// * `xml` - The XML document to parse.
+    /// * `options` - Controls how strictly element values are checked and
+    ///   whether `lastmod` values are normalized.
+    ///
+    /// # Returns
+    /// The parsed `Sitemap`, or an error under the same conditions as
+    /// [`Sitemap::from_xml`]. In strict mode, a `<loc>` value with
+    /// surrounding whitespace is also an error.
+    pub fn from_xml_with(
+        xml: &str,
+        options: ParseOptions,
+    ) -> SitemapResult<Sitemap> {
+        // A cheap scan for `<url>` tags sizes the entry list up front; it's
+        // only a capacity hint, so prefixed or attributed tags don't matter.
+        let capacity = xml.matches("<url>").count();
+        Self::parse(EventReader::from_str(xml), capacity, options)
+    }
+
+    /// Parses a sitemap from raw, possibly untrusted, XML bytes.
+    ///
+    /// The encoding is taken from the XML declaration, defaulting to UTF-8.
+    /// Arbitrary input, including truncated documents and invalid byte
+    /// sequences, yields an error rather than a panic.
+    ///
+    /// # Arguments
+    /// * `xml` - The XML document to parse.
+    ///
+    /// # Returns
+    /// The parsed `Sitemap`, or an error under the same conditions as
+    /// [`Sitemap::from_xml`].
+    pub fn from_xml_bytes(xml: &[u8]) -> SitemapResult<Sitemap> {
+        let capacity =
+            xml.windows(5).filter(|window| window == b"<url>").count();
+        Self::parse(
+            EventReader::new(xml),
+            capacity,
+            ParseOptions::default(),
+        )
+    }
+
+    /// Builds a sitemap from the events of `reader`.
+    fn parse<R: std::io::Read>(
+        reader: EventReader<R>,
+        capacity: usize,
+        options: ParseOptions,
+    ) -> SitemapResult<Sitemap> {
+        let mut sitemap = Sitemap::with_capacity(capacity);
+        let mut depth = 0usize;
+        let mut entry: Option<PartialEntry> = None;
+        let mut field: Option<String> = None;
+        let mut text = String::new();
+
+        for event in reader {
+            match event? {
+                ReaderEvent::StartElement { name, .. } => {
+                    depth += 1;
+                    let in_sitemap_ns = name
+                        .namespace
+                        .as_deref()
+                        .map_or(true, |ns| ns == SITEMAP_NS);
+                    match depth {
+                        1 if name.local_name != "urlset" => {
+                            return Err(SitemapError::CustomError(
+                                format!(
+                                    "Expected <urlset> root element, found <{}>",
+                                    name.local_name
+                                ),
+                            ));
+                        }
+                        2 if in_sitemap_ns
+                            && name.local_name == "url" =>
+                        {
+                            entry = Some(PartialEntry::default());
+                        }
+                        3 if in_sitemap_ns && entry.is_some() => {
+                            field = Some(name.local_name);
+                            text.clear();
+                        }
+                        _ => {}
+                    }
+                }
+                ReaderEvent::Characters(data)
+                | ReaderEvent::CData(data)
+                    if depth == 3 && field.is_some() =>
+                {
+                    text.push_str(&data);
+                }
+                ReaderEvent::EndElement { .. } => {
+                    match depth {
+                        2 => {
+                            if let Some(partial) = entry.take() {
+                                sitemap.add_entry(partial.finish()?)?;
+                            }
+                        }
+                        3 => {
+                            if let (Some(name), Some(partial)) =
+                                (field.take(), entry.as_mut())
+                            {
+                                partial.set(&name, &text, options)?;
+                            }
+                        }
+                        _ => {}
+                    }
+                    depth = depth.saturating_sub(1);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(sitemap)
+    }
+}
+
+impl IntoIterator for Sitemap {
+    type Item = SiteMapData;
+    type IntoIter = std::vec::IntoIter<SiteMapData>;
+
+    /// Consumes the sitemap, yielding its entries in insertion order.
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Sitemap {
+    type Item = &'a SiteMapData;
+    type IntoIter = std::slice::Iter<'a, SiteMapData>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Returns a rough estimate of the serialized size of `entries` entries.
+fn estimated_size(entries: usize) -> usize {
+    entries
+        .saturating_mul(300) // Rough estimate of average entry size in bytes
+        .min(MAX_SITEMAP_SIZE)
+}
+
+/// The extension elements of an entry, as borrowed by
+/// [`extension_namespaces`] and [`write_url`].
+#[derive(Clone, Copy)]
+struct EntryExtensions<'a> {
+    extra: &'a [ExtraElement],
+    alternates: &'a [Alternate],
+    #[cfg(feature = "image-sitemap")]
+    images: &'a [ImageEntry],
+    videos: &'a [VideoEntry],
+}
+
+impl SiteMapData {
+    /// Returns the extension elements of the entry.
+    fn extensions(&self) -> EntryExtensions<'_> {
+        EntryExtensions {
+            extra: &self.extra,
+            alternates: &self.alternates,
+            #[cfg(feature = "image-sitemap")]
+            images: &self.images,
+            videos: &self.videos,
+        }
+    }
+}
+
+/// Returns the prefixes and namespaces of the extensions used by entries
+/// with the given images, videos, alternates and extra elements.
+///
+/// Fails if an extra element binds a prefix to a different namespace than
+/// another element, or one of the built-in prefixes to a foreign one.
+fn extension_namespaces<'a>(
+    entries: impl Iterator<Item = EntryExtensions<'a>>,
+) -> SitemapResult<Vec<(&'a str, &'a str)>> {
+    #[cfg(feature = "image-sitemap")]
+    let mut images = false;
+    let (mut videos, mut alternates) = (false, false);
+    let mut extra: Vec<(&str, &str)> = Vec::new();
+    for extensions in entries {
+        #[cfg(feature = "image-sitemap")]
+        {
+            images |= !extensions.images.is_empty();
+        }
+        videos |= !extensions.videos.is_empty();
+        alternates |= !extensions.alternates.is_empty();
+        for element in extensions.extra {
+            let (prefix, namespace) = match extra_binding(element)? {
+                Some(binding) => binding,
+                None => continue,
+            };
+            let bound = BUILTIN_NAMESPACES
+                .iter()
+                .chain(extra.iter())
+                .find(|(bound, _)| *bound == prefix);
+            match bound {
+                Some((_, bound)) if *bound == namespace => {}
+                Some((_, bound)) => {
+                    return Err(SitemapError::CustomError(format!(
+                        "Prefix '{}' is bound to both '{}' and '{}'",
+                        prefix, bound, namespace
+                    )))
+                }
+                None => extra.push((prefix, namespace)),
+            }
+        }
+    }
+    let mut namespaces = Vec::new();
+    if alternates {
+        namespaces.push(("xhtml", XHTML_NS));
+    }
+    #[cfg(feature = "image-sitemap")]
+    if images {
+        namespaces.push(("image", IMAGE_NS));
+    }
+    if videos {
+        namespaces.push(("video", VIDEO_NS));
+    }
+    namespaces.extend(extra);
+    Ok(namespaces)
+}
+
+/// The prefixes bound by the extensions this crate models.
+const BUILTIN_NAMESPACES: &[(&str, &str)] = &[
+    ("xhtml", XHTML_NS),
+    #[cfg(feature = "image-sitemap")]
+    ("image", IMAGE_NS),
+    ("video", VIDEO_NS),
+];
+
+/// Returns the first `depth` segments of the path of `loc`, such as
+/// `/blog`, or `/` for the root.
+fn section_of(loc: &Url, depth: usize) -> String {
+    let segments: Vec<&str> = loc
+        .path()
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .take(depth)
+        .collect();
+    format!("/{}", segments.join("/"))
+}
+
+/// Writes the XML declaration, declaring `encoding`, and the opening
+/// `<urlset>` element, binding each of the `(prefix, namespace)` pairs in
+/// `namespaces`.
+fn write_urlset_start<W: Write>(
+    writer: &mut EventWriter<W>,
+    encoding: &str,
+    namespaces: &[(&str, &str)],
+) -> SitemapResult<()> {
+    writer.write(XmlEvent::StartDocument {
+        version: xml::common::XmlVersion::Version10,
+        encoding: Some(encoding),
+        standalone: None,
+    })?;
+    let mut start =
+        XmlEvent::start_element("urlset").default_ns(SITEMAP_NS);
+    for &(prefix, namespace) in namespaces {
+        start = start.ns(prefix, namespace);
+    }
+    writer.write(start)?;
+    Ok(())
+}
+
+/// Writes a simple `<name>text</name>` element.
+fn write_element<W: Write>(
+    writer: &mut EventWriter<W>,
+    name: &str,
+    text: &str,
+) -> SitemapResult<()> {
+    check_xml_text(name, text)?;
+    writer.write(XmlEvent::start_element(name))?;
+    writer.write(XmlEvent::characters(text))?;
+    writer.write(XmlEvent::end_element())?;
+    Ok(())
+}
+
+/// Checks that `text`, the content of the `element` element, only holds
+/// characters allowed in XML 1.0.
+///
+/// `xml-rs` escapes markup characters such as `&` and `<`, but writes
+/// control characters through as is, which makes the document malformed.
+/// They can't be escaped either, as XML 1.0 doesn't allow them even as
+/// character references.
+fn check_xml_text(element: &str, text: &str) -> SitemapResult<()> {
+    let invalid = text.chars().find(|&c| {
+        matches!(c, '\u{0}'..='\u{8}' | '\u{b}' | '\u{c}' | '\u{e}'..='\u{1f}')
+            || matches!(c, '\u{fffe}' | '\u{ffff}')
+    });
+    match invalid {
+        Some(character) => Err(SitemapError::InvalidXmlCharacter {
+            character,
+            element: element.to_string(),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Writes a `<url>` element with its `<loc>` and `<lastmod>`, followed by
+/// `<changefreq>` and `<priority>` when given, and the extension elements:
+/// the extra elements, the alternates, the images and the videos.
+///
+/// When `cdata_loc` is set, the location is wrapped in a CDATA section
+/// instead of being escaped. Alternates, images and videos declare their
+/// prefixes themselves when `declare_ns` is set.
+#[allow(clippy::too_many_arguments)]
+fn write_url<W: Write>(
+    writer: &mut EventWriter<W>,
+    loc: &str,
+    lastmod: &str,
+    changefreq: Option<&str>,
+    priority: Option<f32>,
+    extensions: EntryExtensions<'_>,
+    declare_ns: bool,
+    cdata_loc: bool,
+) -> SitemapResult<()> {
+    #[cfg(feature = "image-sitemap")]
+    if extensions.images.len() > MAX_IMAGES_PER_URL {
+        return Err(SitemapError::TooManyImages {
+            url: loc.to_string(),
+            count: extensions.images.len(),
+        });
+    }
+    writer.write(XmlEvent::start_element("url"))?;
+    if cdata_loc {
+        writer.write(XmlEvent::start_element("loc"))?;
+        writer.write(XmlEvent::cdata(loc))?;
+        writer.write(XmlEvent::end_element())?;
+    } else {
+        write_element(writer, "loc", loc)?;
+    }
+    write_element(writer, "lastmod", lastmod)?;
+    if let Some(changefreq) = changefreq {
+        write_element(writer, "changefreq", changefreq)?;
+    }
+    if let Some(priority) = priority {
+        write_element(writer, "priority", &priority_text(priority))?;
+    }
+    for element in extensions.extra {
+        let binding = extra_binding(element)?;
+        check_xml_text(&element.name, &element.value)?;
+        let start = XmlEvent::start_element(element.name.as_str());
+        match binding {
+            Some((prefix, namespace)) if declare_ns => {
+                writer.write(start.ns(prefix, namespace))?;
+            }
+            _ => writer.write(start)?,
+        }
+        if !element.value.is_empty() {
+            writer.write(XmlEvent::characters(&element.value))?;
+        }
+        writer.write(XmlEvent::end_element())?;
+    }
+    for alternate in extensions.alternates {
+        write_alternate(writer, alternate, declare_ns)?;
+    }
+    #[cfg(feature = "image-sitemap")]
+    for image in extensions.images {
+        write_image(writer, image, declare_ns)?;
+    }
+    for video in extensions.videos {
+        write_video(writer, loc, video, declare_ns)?;
+    }
+    writer.write(XmlEvent::end_element())?;
+    Ok(())
+}
+
+/// Checks that `element` has a valid name, and a namespace exactly when
+/// the name is prefixed.
+fn check_extra(element: &ExtraElement) -> SitemapResult<()> {
+    let _ = extra_binding(element)?;
+    Ok(())
+}
+
+/// Returns the prefix of `element` and the namespace bound to it, or
+/// `None` for an unprefixed element.
+///
+/// Fails if the name is invalid, a prefixed element has no namespace, an
+/// unprefixed one has a namespace, or the prefix is reserved by XML.
+fn extra_binding(
+    element: &ExtraElement,
+) -> SitemapResult<Option<(&str, &str)>> {
+    check_element_name(&element.name)?;
+    let error = |message: &str| {
+        Err(SitemapError::CustomError(format!(
+            "Extension element '{}' {}",
+            element.name, message
+        )))
+    };
+    match (element.name.split_once(':'), element.namespace.as_deref()) {
+        (None, None) => Ok(None),
+        (None, Some(_)) => error("has a namespace but no prefix"),
+        (Some(_), None) => error("has no namespace for its prefix"),
+        (Some((prefix, _)), Some(_))
+            if prefix.to_ascii_lowercase().starts_with("xml") =>
+        {
+            error("uses a reserved prefix")
+        }
+        (Some(_), Some("")) => error("has an empty namespace"),
+        (Some((prefix, _)), Some(namespace)) => {
+            Ok(Some((prefix, namespace)))
+        }
+    }
+}
+
+/// Checks that `name` is a valid XML element name, with at most one
+/// prefix separator.
+fn check_element_name(name: &str) -> SitemapResult<()> {
+    let is_start = |c: char| c.is_alphabetic() || c == '_';
+    let is_name = |c: char| {
+        is_start(c) || c.is_numeric() || matches!(c, '-' | '.')
+    };
+    let valid = name.split(':').count() <= 2
+        && name.split(':').all(|part| {
+            let mut chars = part.chars();
+            chars.next().map_or(false, is_start) && chars.all(is_name)
+        });
+    if valid {
+        Ok(())
+    } else {
+        Err(SitemapError::CustomError(format!(
+            "Invalid XML element name '{}'",
+            name
+        )))
+    }
+}
+
+/// Checks whether `url` holds a `%` that isn't followed by two hex digits.
+///
+/// `Url` leaves such sequences alone when parsing, so `%2` or `%GG`
+/// survive into the serialized location.
+fn has_malformed_escape(url: &str) -> bool {
+    let bytes = url.as_bytes();
+    bytes.iter().enumerate().any(|(i, &byte)| {
+        byte == b'%'
+            && !bytes.get(i + 1..i + 3).map_or(false, |hex| {
+                hex.iter().all(u8::is_ascii_hexdigit)
+            })
+    })
+}
+
+/// The priority search engines assume when `<priority>` is missing.
+const DEFAULT_PRIORITY: f32 = 0.5;
+
+/// Checks that `priority` lies within the 0.0 to 1.0 range.
+fn check_priority(priority: f32) -> SitemapResult<f32> {
+    if (0.0..=1.0).contains(&priority) {
+        Ok(priority)
+    } else {
+        Err(SitemapError::InvalidPriority(priority))
+    }
+}
+
+/// Formats a priority with at least one decimal place, such as `1.0`.
+fn priority_text(priority: f32) -> String {
+    let text = priority.to_string();
+    if text.contains('.') {
+        text
+    } else {
+        text + ".0"
+    }
+}
+
+/// Converts the serialized document into a `String`, enforcing the size limit.
+fn into_checked_xml(output: Vec<u8>) -> SitemapResult<String> {
+    // Convert the output Vec<u8> directly into a string without intermediate allocations
+    let xml = unsafe { String::from_utf8_unchecked(output) };
+
+    // Check size before returning to ensure the sitemap isn't too large
+    if xml.len() > MAX_SITEMAP_SIZE {
+        return Err(SitemapError::SitemapTooLarge {
+            size: xml.len(),
+            limit: MAX_SITEMAP_SIZE,
+        });
+    }
+
+    Ok(xml)
+}
+
+/// A writer that counts the bytes passed through to `inner`.
+#[derive(Debug)]
+struct ByteCounter<W> {
+    inner: W,
+    count: usize,
+}
+
+impl<W> ByteCounter<W> {
+    fn new(inner: W) -> Self {
+        ByteCounter { inner, count: 0 }
+    }
+}
+
+impl<W: Write> Write for ByteCounter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.count += written;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Compresses `data` with gzip at the given level.
+#[cfg(feature = "gzip")]
+fn gzip(
+    data: &[u8],
+    level: flate2::Compression,
+) -> SitemapResult<Vec<u8>> {
+    let mut encoder = flate2::write::GzEncoder::new(
+        Vec::with_capacity(data.len() / 4),
+        level,
+    );
+    encoder.write_all(data)?;
+    Ok(encoder.finish()?)
+}
+
+/// Returns `lastmod` as it should be written for the given precision.
+fn lastmod_text(
+    lastmod: &str,
+    precision: LastmodPrecision,
+) -> Cow<'_, str> {
+    match precision {
+        LastmodPrecision::DateOnly if lastmod.contains('T') => {
+            Cow::Owned(convert_date_format(lastmod))
+        }
+        _ => Cow::Borrowed(lastmod),
+    }
+}
+
+/// Returns the lowercased file extension of the URL's last path segment.
+fn url_extension(url: &Url) -> Option<String> {
+    let segment = url.path_segments()?.next_back()?;
+    match segment.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() && !ext.is_empty() => {
+            Some(ext.to_lowercase())
+        }
+        _ => None,
+    }
+}
+
+/// The fields of a `<url>` element collected while parsing.
+#[derive(Debug, Default)]
+struct PartialEntry {
+    changefreq: Option<ChangeFreq>,
+    lastmod: Option<String>,
+    loc: Option<Url>,
+    priority: Option<f32>,
+}
+
+impl PartialEntry {
+    /// Stores the text of the child element `name`.
+    fn set(
+        &mut self,
+        name: &str,
+        text: &str,
+        options: ParseOptions,
+    ) -> SitemapResult<()> {
+        match name {
+            "loc" if options.strict && text.trim() != text => {
+                return Err(SitemapError::CustomError(format!(
+                    "Whitespace around <loc> value '{}'",
+                    text
+                )));
+            }
+            "loc" => self.loc = Some(Url::parse(text.trim())?),
+            "lastmod" if options.normalize_dates => {
+                self.lastmod = Some(convert_date_format(text.trim()))
+            }
+            "lastmod" => self.lastmod = Some(text.to_string()),
+            "changefreq" => {
+                self.changefreq = Some(text.trim().parse()?)
+            }
+            // A bad priority only loses a hint to crawlers, so the entry
+            // is kept without one.
+            "priority" => {
+                self.priority =
+                    text.trim().parse().ok().and_then(|priority| {
+                        check_priority(priority).ok()
+                    });
+                if self.priority.is_none() {
+                    warn!(
+                        "Ignoring invalid <priority> value '{}'",
+                        text
+                    );
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Builds the entry, failing if no `<loc>` was found.
+    fn finish(self) -> SitemapResult<SiteMapData> {
+        let loc = self.loc.ok_or_else(|| {
+            SitemapError::CustomError(
+                "Missing <loc> in <url> entry".to_string(),
+            )
+        })?;
+        Ok(SiteMapData {
+            changefreq: self.changefreq.unwrap_or_default(),
+            lastmod: self.lastmod.unwrap_or_default(),
+            loc,
+            priority: self.priority,
+            extra: Vec::new(),
+            images: Vec::new(),
+            videos: Vec::new(),
+            alternates: Vec::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sitemap::writer::SitemapWriter;
+
+    const MOBILE_NS: &str =
+        "http://www.google.com/schemas/sitemap-mobile/1.0";
+    use dtt::dtt_now;
+
+    #[test]
+    fn test_create_site_map_data() -> SitemapResult<()> {
+        let mut metadata = HashMap::new();
+        let _ = metadata.insert(
+            "last_build_date".to_string(),
+            "20 May 2023".to_string(),
+        );
+        let _ = metadata
+            .insert("changefreq".to_string(), "weekly".to_string());
+        let _ = metadata.insert(
+            "permalink".to_string(),
+            "https://example.com".to_string(),
+        );
+
+        let site_map_data = create_site_map_data(&metadata)?;
+
+        assert_eq!(site_map_data.lastmod, "2023-05-20");
+        assert_eq!(site_map_data.changefreq, ChangeFreq::Weekly);
+        assert_eq!(
+            site_map_data.loc,
+            Url::parse("https://example.com")?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_change_freq_variants() -> SitemapResult<()> {
+        assert_eq!(ChangeFreq::VARIANTS.len(), 7);
+        for variant in ChangeFreq::VARIANTS {
+            let freq: ChangeFreq = variant.parse()?;
+            assert_eq!(freq.as_str(), *variant);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_change_freq_aliases() -> SitemapResult<()> {
+        let mut aliases = ChangeFreqAliases::new();
+        aliases.register("nightly", ChangeFreq::Daily);
+
+        assert_eq!(aliases.parse("nightly")?, ChangeFreq::Daily);
+        assert_eq!(aliases.parse("NIGHTLY")?, ChangeFreq::Daily);
+        assert_eq!(aliases.parse("hourly")?, ChangeFreq::Hourly);
+        assert!(matches!(
+            aliases.parse("realtime"),
+            Err(SitemapError::InvalidChangeFreq(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_convert_date_format() {
+        assert_eq!(convert_date_format("20 May 2023"), "2023-05-20");
+        assert_eq!(convert_date_format("2023-05-20"), "2023-05-20");
+        assert_eq!(convert_date_format("Invalid Date"), "Invalid Date");
+    }
+
+    #[test]
+    fn test_sitemap_to_xml() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new();
+        sitemap.add_entry(SiteMapData {
+            loc: Url::parse("https://example.com")?,
+            lastmod: "2023-05-20".to_string(),
+            changefreq: ChangeFreq::Weekly,
+            priority: None,
+            extra: Vec::new(),
+            images: Vec::new(),
+            videos: Vec::new(),
+            alternates: Vec::new(),
+        })?;
+
+        let xml = sitemap.to_xml()?;
+        assert!(xml.contains("<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">"));
+        assert!(xml.contains("<url>"));
+        assert!(xml.contains("<loc>https://example.com/</loc>"));
+        assert!(xml.contains("<lastmod>2023-05-20</lastmod>"));
+        assert!(xml.contains("<changefreq>weekly</changefreq>"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_sitemap_size_limit() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new();
+        for i in 0..50_000 {
+            sitemap.add_entry(SiteMapData {
+                loc: Url::parse(&format!("https://example.com/{}", i))?,
+                lastmod: "2023-05-20".to_string(),
+                changefreq: ChangeFreq::Weekly,
+                priority: None,
+                extra: Vec::new(),
+                images: Vec::new(),
+                videos: Vec::new(),
+                alternates: Vec::new(),
+            })?;
+        }
+        assert!(matches!(
+            sitemap.add_entry(SiteMapData {
+                loc: Url::parse("https://example.com/toomany")?,
+                lastmod: "2023-05-20".to_string(),
+                changefreq: ChangeFreq::Weekly,
+                priority: None,
+                extra: Vec::new(),
+                images: Vec::new(),
+                videos: Vec::new(),
+                alternates: Vec::new(),
+            }),
+            Err(SitemapError::MaxUrlLimitExceeded(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_partition_valid() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new();
+        sitemap.add_entry(SiteMapData {
+            loc: Url::parse("https://example.com/good")?,
+            lastmod: "2023-05-20".to_string(),
+            changefreq: ChangeFreq::Weekly,
+            priority: None,
+            extra: Vec::new(),
+            images: Vec::new(),
+            videos: Vec::new(),
+            alternates: Vec::new(),
+        })?;
+        sitemap.add_entry(SiteMapData {
+            loc: Url::parse("https://example.com/bad")?,
+            lastmod: "not a date".to_string(),
+            changefreq: ChangeFreq::Weekly,
+            priority: None,
+            extra: Vec::new(),
+            images: Vec::new(),
+            videos: Vec::new(),
+            alternates: Vec::new(),
+        })?;
+
+        let (valid, invalid) = sitemap.partition_valid();
+        assert_eq!(valid.len(), 1);
+        assert_eq!(valid[0].loc.path(), "/good");
+        assert_eq!(invalid.len(), 1);
+        assert_eq!(invalid[0].0.loc.path(), "/bad");
+        assert_eq!(
+            invalid[0].1,
+            ValidationIssue::InvalidLastmod("not a date".to_string())
+        );
+        assert_eq!(sitemap.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_entry() -> SitemapResult<()> {
+        let entry = SiteMapData {
+            loc: Url::parse("ftp://example.com/file")?,
+            lastmod: "2023-05-20".to_string(),
+            changefreq: ChangeFreq::Weekly,
+            priority: None,
+            extra: Vec::new(),
+            images: Vec::new(),
+            videos: Vec::new(),
+            alternates: Vec::new(),
+        };
+        assert_eq!(
+            entry.validate(),
+            Err(ValidationIssue::InvalidScheme("ftp".to_string()))
+        );
+
+        let entry = SiteMapData {
+            loc: Url::parse(&format!(
+                "https://example.com/{}",
+                "a".repeat(MAX_URL_LENGTH)
+            ))?,
+            lastmod: "2023-05-20T10:00:00Z".to_string(),
+            changefreq: ChangeFreq::Weekly,
+            priority: None,
+            extra: Vec::new(),
+            images: Vec::new(),
+            videos: Vec::new(),
+            alternates: Vec::new(),
+        };
+        assert!(matches!(
+            entry.validate(),
+            Err(ValidationIssue::UrlTooLong(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_flags_malformed_escapes() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new();
+        for loc in [
+            "https://example.com/a%2",
+            "https://example.com/b%GG",
+            "https://example.com/c%20d?q=%7e",
+        ] {
+            sitemap.add_url(Url::parse(loc)?)?;
+        }
+
+        let issues =
+            sitemap.validate(&Url::parse("https://example.com/")?);
+        assert_eq!(
+            issues,
+            vec![
+                ValidationIssue::MalformedEscape(
+                    "https://example.com/a%2".to_string()
+                ),
+                ValidationIssue::MalformedEscape(
+                    "https://example.com/b%GG".to_string()
+                ),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_url_uses_default_changefreq() -> SitemapResult<()> {
+        let mut sitemap =
+            Sitemap::new().with_default_changefreq(ChangeFreq::Daily);
+        sitemap.add_url(Url::parse("https://example.com/news")?)?;
+
+        let entry = &sitemap.entries[0];
+        assert_eq!(entry.changefreq, ChangeFreq::Daily);
+        assert!(entry.validate().is_ok());
+
+        let mut sitemap = Sitemap::new();
+        sitemap.add_url(Url::parse("https://example.com")?)?;
+        assert_eq!(sitemap.entries[0].changefreq, ChangeFreq::Weekly);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sitemap_too_large_reports_size() -> SitemapResult<()> {
+        let padding = "a".repeat(250);
+        let build = |count: usize| -> SitemapResult<Sitemap> {
+            let mut sitemap = Sitemap::new();
+            for i in 0..count {
+                sitemap.add_entry(SiteMapData {
+                    loc: Url::parse(&format!(
+                        "https://example.com/{}/{:05}",
+                        padding, i
+                    ))?,
+                    lastmod: "2023-05-20".to_string(),
+                    changefreq: ChangeFreq::Weekly,
+                    priority: None,
+                    extra: Vec::new(),
+                    images: Vec::new(),
+                    videos: Vec::new(),
+                    alternates: Vec::new(),
+                })?;
+            }
+            Ok(sitemap)
+        };
+
+        // Every entry serializes to the same length, so the full size can
+        // be derived from a one- and a two-entry sitemap.
+        let one = build(1)?.to_xml()?.len();
+        let per_entry = build(2)?.to_xml()?.len() - one;
+        let expected = one + per_entry * (MAX_URLS - 1);
+
+        match build(MAX_URLS)?.to_xml() {
+            Err(SitemapError::SitemapTooLarge { size, limit }) => {
+                assert_eq!(size, expected);
+                assert_eq!(limit, MAX_SITEMAP_SIZE);
+            }
+            other => {
+                panic!("Expected SitemapTooLarge, got {:?}", other)
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_xml_round_trip() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new();
+        sitemap.add_entry(SiteMapData {
+            loc: Url::parse("https://example.com/?a=1&b=2")?,
+            lastmod: "2023-05-20".to_string(),
+            changefreq: ChangeFreq::Daily,
+            priority: None,
+            extra: Vec::new(),
+            images: Vec::new(),
+            videos: Vec::new(),
+            alternates: Vec::new(),
+        })?;
+        sitemap.add_entry(SiteMapData {
+            loc: Url::parse("https://example.com/about")?,
+            lastmod: "2023-05-21".to_string(),
+            changefreq: ChangeFreq::Never,
+            priority: None,
+            extra: Vec::new(),
+            images: Vec::new(),
+            videos: Vec::new(),
+            alternates: Vec::new(),
+        })?;
+
+        let parsed = Sitemap::from_xml(&sitemap.to_xml()?)?;
+        assert_eq!(parsed.entries, sitemap.entries);
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_xml_large_round_trip() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new();
+        for i in 0..5_000 {
+            sitemap.add_entry(SiteMapData {
+                loc: Url::parse(&format!(
+                    "https://example.com/section-{}/page?id={}&ref=home",
+                    i % 7,
+                    i
+                ))?,
+                lastmod: format!("2024-{:02}-{:02}", i % 12 + 1, i % 28 + 1),
+                changefreq: ChangeFreq::VARIANTS[i % 7].parse()?,
+                priority: None,
+                extra: Vec::new(),
+                images: Vec::new(),
+                videos: Vec::new(),
+                alternates: Vec::new(),
+            })?;
+        }
+
+        let xml = sitemap.to_xml()?;
+        let parsed = Sitemap::from_xml(&xml)?;
+        assert_eq!(parsed.entries, sitemap.entries);
+        assert_eq!(parsed.to_xml()?, xml);
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_xml_skips_unmodelled_elements() -> SitemapResult<()> {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9"
+        xmlns:image="http://www.google.com/schemas/sitemap-image/1.1">
+  <url>
+    <loc>https://example.com/</loc>
+    <lastmod>2023-05-20</lastmod>
+    <priority>0.8</priority>
+    <image:image><image:loc>https://example.com/a.png</image:loc></image:image>
+  </url>
+</urlset>"#;
+
+        let parsed = Sitemap::from_xml(xml)?;
+        assert_eq!(parsed.len(), 1);
+        assert_eq!(
+            parsed.entries[0].loc.as_str(),
+            "https://example.com/"
+        );
+        assert_eq!(parsed.entries[0].lastmod, "2023-05-20");
+        assert_eq!(parsed.entries[0].changefreq, ChangeFreq::Weekly);
+        assert_eq!(parsed.entries[0].priority, Some(0.8));
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_xml_ignores_invalid_priority() -> SitemapResult<()> {
+        for priority in ["high", "1.5"] {
+            let xml = format!(
+                "<urlset><url><loc>https://example.com/</loc>\
+                 <priority>{}</priority></url></urlset>",
+                priority
+            );
+            let parsed = Sitemap::from_xml(&xml)?;
+            assert_eq!(parsed.len(), 1);
+            assert_eq!(parsed.entries[0].priority, None);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_xml_errors() {
+        assert!(matches!(
+            Sitemap::from_xml("<sitemapindex></sitemapindex>"),
+            Err(SitemapError::CustomError(_))
+        ));
+        assert!(matches!(
+            Sitemap::from_xml(
+                "<urlset><url><lastmod>2023-05-20</lastmod></url></urlset>"
+            ),
+            Err(SitemapError::CustomError(_))
+        ));
+        assert!(matches!(
+            Sitemap::from_xml(
+                "<urlset><url><loc>nope</loc></url></urlset>"
+            ),
+            Err(SitemapError::UrlError(_))
+        ));
+        assert!(matches!(
+            Sitemap::from_xml("<urlset><url>"),
+            Err(SitemapError::XmlParseError(_))
+        ));
+    }
+
+    #[test]
+    fn test_add_url_document_defaults() -> SitemapResult<()> {
+        let mut sitemap =
+            Sitemap::new().with_default_changefreq(ChangeFreq::Daily);
+        sitemap
+            .add_url(Url::parse("https://example.com/report.PDF")?)?;
+        sitemap.add_url(Url::parse("https://example.com/blog/")?)?;
+        assert_eq!(sitemap.entries[0].changefreq, ChangeFreq::Monthly);
+        assert_eq!(
+            sitemap.entries[0].priority,
+            Some(DOCUMENT_PRIORITY)
+        );
+        assert_eq!(sitemap.entries[1].changefreq, ChangeFreq::Daily);
+        assert_eq!(sitemap.entries[1].priority, None);
+
+        let mut sitemap = Sitemap::new()
+            .with_extension_changefreq("pdf", ChangeFreq::Yearly)
+            .with_extension_priority(".pdf", 0.1)?;
+        sitemap
+            .add_url(Url::parse("https://example.com/report.pdf")?)?;
+        assert_eq!(sitemap.entries[0].changefreq, ChangeFreq::Yearly);
+        assert_eq!(sitemap.entries[0].priority, Some(0.1));
+        assert!(Sitemap::new()
+            .with_extension_priority("pdf", 2.0)
+            .is_err());
+
+        let mut sitemap = Sitemap::new().without_extension_defaults();
+        sitemap
+            .add_url(Url::parse("https://example.com/photo.jpg")?)?;
+        assert_eq!(sitemap.entries[0].changefreq, ChangeFreq::Weekly);
+        assert_eq!(sitemap.entries[0].priority, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_with_capacity_is_capped() -> SitemapResult<()> {
+        let sitemap = Sitemap::with_capacity(usize::MAX);
+        assert!(sitemap.capacity() <= MAX_URLS);
+
+        let mut sitemap = Sitemap::with_capacity(10);
+        sitemap.add_url(Url::parse("https://example.com")?)?;
+        sitemap.reserve(usize::MAX);
+        assert!(sitemap.capacity() <= MAX_URLS);
+        assert_eq!(sitemap.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_lastmod_precision() -> SitemapResult<()> {
+        let entry = SiteMapData {
+            loc: Url::parse("https://example.com")?,
+            lastmod: "2024-10-08T12:30:00Z".to_string(),
+            changefreq: ChangeFreq::Weekly,
+            priority: None,
+            extra: Vec::new(),
+            images: Vec::new(),
+            videos: Vec::new(),
+            alternates: Vec::new(),
+        };
+
+        let mut sitemap = Sitemap::new();
+        sitemap.add_entry(entry.clone())?;
+        assert!(sitemap
+            .to_xml()?
+            .contains("<lastmod>2024-10-08</lastmod>"));
+
+        let mut sitemap = Sitemap::new()
+            .with_lastmod_precision(LastmodPrecision::Full);
+        sitemap.add_entry(entry)?;
+        assert!(sitemap
+            .to_xml()?
+            .contains("<lastmod>2024-10-08T12:30:00Z</lastmod>"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_append_urls_from_reader() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new();
+        let input = std::io::Cursor::new(
+            "https://example.com\n# skipped\nhttps://example.com/docs\n",
+        );
+        assert_eq!(sitemap.append_urls_from_reader(input)?, 2);
+        assert_eq!(sitemap.len(), 2);
+
+        let invalid =
+            std::io::Cursor::new("https://example.org\nnot a url\n");
+        assert!(sitemap.append_urls_from_reader(invalid).is_err());
+        assert_eq!(sitemap.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_changefreq_renderer() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new();
+        sitemap.add_entry(SiteMapData {
+            loc: Url::parse("https://example.com")?,
+            lastmod: "2024-10-08".to_string(),
+            changefreq: ChangeFreq::Daily,
+            priority: None,
+            extra: Vec::new(),
+            images: Vec::new(),
+            videos: Vec::new(),
+            alternates: Vec::new(),
+        })?;
+        sitemap.with_changefreq_renderer(|freq| {
+            freq.as_str().to_uppercase()
+        });
+
+        let xml = sitemap.to_xml()?;
+        assert!(xml.contains("<changefreq>DAILY</changefreq>"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_add_entry_rejects_long_urls() -> SitemapResult<()> {
+        let url_of_len = |len: usize| {
+            let base = "https://example.com/";
+            Url::parse(&format!(
+                "{}{}",
+                base,
+                "a".repeat(len - base.len())
+            ))
+        };
+        let mut sitemap = Sitemap::new();
+        sitemap.add_url(url_of_len(MAX_URL_LENGTH)?)?;
+
+        let long = url_of_len(MAX_URL_LENGTH + 1)?;
+        match sitemap.add_url(long.clone()) {
+            Err(SitemapError::UrlTooLong { url, len }) => {
+                assert_eq!(url, long.as_str());
+                assert_eq!(len, MAX_URL_LENGTH + 1);
+            }
+            other => panic!("Expected UrlTooLong, got {:?}", other),
+        }
+        assert_eq!(sitemap.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_suppress_changefreq() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new();
+        for (loc, changefreq) in [
+            ("https://example.com/live", ChangeFreq::Always),
+            ("https://example.com/news", ChangeFreq::Daily),
+        ] {
+            sitemap.add_entry(SiteMapData::new(
+                Url::parse(loc)?,
+                "2024-10-08",
+                changefreq,
+            ))?;
+        }
+        sitemap.suppress_changefreq(&[ChangeFreq::Always]);
+
+        let xml = sitemap.to_xml()?;
+        assert!(xml.contains(
+            "<loc>https://example.com/live</loc>\
+             <lastmod>2024-10-08</lastmod></url>"
+        ));
+        assert!(xml.contains("<changefreq>daily</changefreq>"));
+        assert!(!xml.contains("always"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_checksum_ignores_order() -> SitemapResult<()> {
+        let entries = [
+            ("https://example.com/a", ChangeFreq::Daily),
+            ("https://example.com/b", ChangeFreq::Weekly),
+            ("https://example.com/c", ChangeFreq::Monthly),
+        ];
+        let mut forward = Sitemap::new();
+        let mut reverse = Sitemap::new();
+        for (loc, changefreq) in entries {
+            forward.add_entry(SiteMapData {
+                loc: Url::parse(loc)?,
+                lastmod: "2024-10-08".to_string(),
+                changefreq,
+                priority: None,
+                extra: Vec::new(),
+                images: Vec::new(),
+                videos: Vec::new(),
+                alternates: Vec::new(),
+            })?;
+        }
+        for (loc, changefreq) in entries.into_iter().rev() {
+            reverse.add_entry(SiteMapData {
+                loc: Url::parse(loc)?,
+                lastmod: "2024-10-08".to_string(),
+                changefreq,
+                priority: None,
+                extra: Vec::new(),
+                images: Vec::new(),
+                videos: Vec::new(),
+                alternates: Vec::new(),
+            })?;
+        }
+
+        assert_ne!(forward.to_xml()?, reverse.to_xml()?);
+        assert_eq!(forward.checksum()?, reverse.checksum()?);
+        assert_eq!(forward.checksum()?.len(), 64);
+        assert_ne!(forward.checksum()?, Sitemap::new().checksum()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_assert_single_host() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new();
+        sitemap.add_url(Url::parse("https://example.com/")?)?;
+        sitemap.add_url(Url::parse("https://example.com/about")?)?;
+        assert!(sitemap.assert_single_host().is_ok());
+
+        sitemap.add_url(Url::parse("https://cdn.example.com/")?)?;
+        sitemap.add_url(Url::parse("https://example.org/")?)?;
+        match sitemap.assert_single_host() {
+            Err(SitemapError::CustomError(message)) => {
+                assert!(
+                    message.contains("cdn.example.com, example.org")
+                );
+            }
+            other => panic!("Expected CustomError, got {:?}", other),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_assert_all_https() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new();
+        sitemap.add_url(Url::parse("https://example.com/")?)?;
+        sitemap.add_url(Url::parse("https://example.com/about")?)?;
+        assert!(sitemap.assert_all_https().is_ok());
+
+        sitemap.add_url(Url::parse("http://example.com/legacy")?)?;
+        match sitemap.assert_all_https() {
+            Err(SitemapError::CustomError(message)) => {
+                assert!(message.contains("1 http URL(s)"));
+                assert!(message.ends_with("http://example.com/legacy"));
+            }
+            other => panic!("Expected CustomError, got {:?}", other),
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_to_json() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new();
+        sitemap.add_entry(
+            SiteMapData::new(
+                Url::parse("https://example.com/")?,
+                "2024-10-08",
+                ChangeFreq::Daily,
+            )
+            .with_priority(0.8)?,
+        )?;
+        sitemap
+            .add_url(Url::parse("https://example.com/\"quoted\"")?)?;
+
+        let json = sitemap.to_json()?;
+        assert!(json.starts_with(
+            r#"[{"changefreq":"daily","lastmod":"2024-10-08","loc":"https://example.com/","priority":0.8},"#
+        ));
+        assert!(json.contains(r#""loc":"https://example.com/%22quoted%22","priority":null}]"#));
+        assert_eq!(Sitemap::new().to_json()?, "[]");
+        Ok(())
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_compress_estimate_close_to_gzip_size() -> SitemapResult<()>
+    {
+        let mut sitemap = Sitemap::new();
+        for i in 0..1_000 {
+            sitemap.add_entry(SiteMapData {
+                loc: Url::parse(&format!(
+                    "https://example.com/articles/{}",
+                    i
+                ))?,
+                lastmod: "2024-10-08".to_string(),
+                changefreq: ChangeFreq::Weekly,
+                priority: None,
+                extra: Vec::new(),
+                images: Vec::new(),
+                videos: Vec::new(),
+                alternates: Vec::new(),
+            })?;
+        }
+
+        let actual = sitemap.to_xml_gzip()?.len();
+        let estimate = sitemap.compress_estimate()?;
+        assert!(actual < sitemap.to_xml()?.len());
+        assert!(
+            estimate.max(actual) - estimate.min(actual) <= actual / 4,
+            "estimate {} too far from actual {}",
+            estimate,
+            actual
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_append_entry_validated() -> SitemapResult<()> {
+        let entry = |loc: &str, lastmod: &str| -> SitemapResult<_> {
+            Ok(SiteMapData {
+                loc: Url::parse(loc)?,
+                lastmod: lastmod.to_string(),
+                changefreq: ChangeFreq::Weekly,
+                priority: None,
+                extra: Vec::new(),
+                images: Vec::new(),
+                videos: Vec::new(),
+                alternates: Vec::new(),
+            })
+        };
+        let mut sitemap = Sitemap::new();
+
+        assert_eq!(
+            sitemap.append_entry_validated(entry(
+                "https://example.com",
+                "2024-10-08"
+            )?),
+            Ok(())
+        );
+        assert_eq!(
+            sitemap.append_entry_validated(entry(
+                "https://example.com/",
+                "2024-10-09"
+            )?),
+            Err(ValidationIssue::Duplicate(
+                "https://example.com/".to_string()
+            ))
+        );
+        assert_eq!(
+            sitemap.append_entry_validated(entry(
+                "ftp://example.com/file",
+                "2024-10-08"
+            )?),
+            Err(ValidationIssue::InvalidScheme("ftp".to_string()))
+        );
+        let long_url =
+            format!("https://example.com/{}", "a".repeat(2_048));
+        assert_eq!(
+            sitemap.append_entry_validated(entry(
+                &long_url,
+                "2024-10-08"
+            )?),
+            Err(ValidationIssue::UrlTooLong(long_url.len()))
+        );
+        assert_eq!(
+            sitemap.append_entry_validated(entry(
+                "https://example.com/news",
+                "last week"
+            )?),
+            Err(ValidationIssue::InvalidLastmod(
+                "last week".to_string()
+            ))
+        );
+        assert_eq!(sitemap.len(), 1);
+
+        let mut full = Sitemap::new();
+        for i in 0..MAX_URLS {
+            full.add_entry(entry(
+                &format!("https://example.com/{}", i),
+                "2024-10-08",
+            )?)?;
+        }
+        assert_eq!(
+            full.append_entry_validated(entry(
+                "https://example.com/extra",
+                "2024-10-08"
+            )?),
+            Err(ValidationIssue::SitemapFull)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_cdata_loc() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new().with_cdata_loc(true);
+        sitemap.add_entry(SiteMapData {
+            loc: Url::parse("https://example.com/?a=1&b=2")?,
+            lastmod: "2024-10-08".to_string(),
+            changefreq: ChangeFreq::Daily,
+            priority: None,
+            extra: Vec::new(),
+            images: Vec::new(),
+            videos: Vec::new(),
+            alternates: Vec::new(),
+        })?;
+
+        let xml = sitemap.to_xml()?;
+        assert!(xml.contains(
+            "<loc><![CDATA[https://example.com/?a=1&b=2]]></loc>"
+        ));
+        assert!(!xml.contains("&amp;"));
+        assert_eq!(
+            Sitemap::from_xml(&xml)?.to_xml()?,
+            sitemap.with_cdata_loc(false).to_xml()?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_lastmod_as_datetime() -> SitemapResult<()> {
+        let mut entry = SiteMapData {
+            loc: Url::parse("https://example.com")?,
+            lastmod: "2024-10-08".to_string(),
+            changefreq: ChangeFreq::Daily,
+            priority: None,
+            extra: Vec::new(),
+            images: Vec::new(),
+            videos: Vec::new(),
+            alternates: Vec::new(),
+        };
+        let parsed = entry
+            .lastmod_as_datetime()
+            .expect("Failed to parse lastmod");
+        assert_eq!(
+            parsed.format("[year]-[month]-[day]")?,
+            "2024-10-08"
+        );
+
+        entry.lastmod = "sometime".to_string();
+        assert!(entry.lastmod_as_datetime().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_by_key() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new();
+        for loc in [
+            "https://example.com/a",
+            "http://example.com/b",
+            "https://example.com/c",
+        ] {
+            sitemap.add_url(Url::parse(loc)?)?;
+        }
+
+        let shards = sitemap
+            .split_by_key(|entry| entry.loc.scheme().to_string());
+        assert_eq!(shards.len(), 2);
+        assert_eq!(shards["https"].len(), 2);
+        assert_eq!(shards["http"].len(), 1);
+        assert!(shards["http"]
+            .to_xml()?
+            .contains("http://example.com/b"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_by_language() -> SitemapResult<()> {
+        let en = Url::parse("https://example.com/en/")?;
+        let fr = Url::parse("https://example.com/fr/")?;
+        let mut sitemap = Sitemap::new();
+        for loc in [&en, &fr] {
+            sitemap.add_entry(
+                SiteMapData::new(
+                    loc.clone(),
+                    "2024-10-08",
+                    ChangeFreq::Weekly,
+                )
+                .with_alternate(Alternate::new("en", en.clone())?)?
+                .with_alternate(Alternate::new("fr", fr.clone())?)?,
+            )?;
+        }
+        sitemap.add_url(Url::parse("https://example.com/about")?)?;
+
+        let languages = sitemap.split_by_language();
+        assert_eq!(languages.len(), 3);
+        assert_eq!(
+            languages["en"].iter_urls().collect::<Vec<_>>(),
+            [&en]
+        );
+        assert_eq!(
+            languages["fr"].iter_urls().collect::<Vec<_>>(),
+            [&fr]
+        );
+        assert_eq!(languages["x-default"].len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_xml_trims_loc_whitespace() -> SitemapResult<()> {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+  <url>
+    <loc>
+      https://example.com/page
+    </loc>
+  </url>
+</urlset>"#;
+
+        let sitemap = Sitemap::from_xml(xml)?;
+        assert!(sitemap
+            .to_xml()?
+            .contains("<loc>https://example.com/page</loc>"));
+
+        let strict = ParseOptions {
+            strict: true,
+            ..ParseOptions::default()
+        };
+        assert!(matches!(
+            Sitemap::from_xml_with(xml, strict),
+            Err(SitemapError::CustomError(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_from_xml_lastmod_formats() -> SitemapResult<()> {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+  <url><loc>https://example.com/a</loc><lastmod>2024-10-08T13:45:00+02:00</lastmod></url>
+  <url><loc>https://example.com/b</loc><lastmod>08 Oct 2024</lastmod></url>
+</urlset>"#;
+
+        let lastmods = |sitemap: Sitemap| -> Vec<String> {
+            sitemap.into_iter().map(|entry| entry.lastmod).collect()
+        };
+        assert_eq!(
+            lastmods(Sitemap::from_xml(xml)?),
+            vec!["2024-10-08T13:45:00+02:00", "08 Oct 2024"]
+        );
+
+        let normalize = ParseOptions {
+            normalize_dates: true,
+            ..ParseOptions::default()
+        };
+        assert_eq!(
+            lastmods(Sitemap::from_xml_with(xml, normalize)?),
+            vec!["2024-10-08", "2024-10-08"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_to_xml_with_config() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new();
+        sitemap.add_url(Url::parse("https://example.com/")?)?;
+
+        let windows = XmlConfig {
+            indent_width: 4,
+            line_ending: "\r\n".to_string(),
+        };
+        let xml = sitemap.to_xml_with(&windows)?;
+        assert!(xml.contains("\r\n    <url>\r\n        <loc>"));
+        assert!(sitemap
+            .to_xml_pretty()?
+            .contains("\n  <url>\n    <loc>"));
+        assert!(!sitemap.to_xml()?.contains('\n'));
+        Ok(())
+    }
+
+    #[test]
+    fn test_iter_urls() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new();
+        sitemap.add_url(Url::parse("https://example.com/b")?)?;
+        sitemap.add_url(Url::parse("https://example.com/a")?)?;
+
+        let urls: Vec<&Url> = sitemap.iter_urls().collect();
+        assert_eq!(urls.len(), 2);
+        assert_eq!(urls[0].as_str(), "https://example.com/b");
+        assert_eq!(urls[1].as_str(), "https://example.com/a");
+        Ok(())
+    }
+
+    #[test]
+    fn test_assign_priority_from_changefreq() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new();
+        for (path, changefreq) in [
+            ("/news", ChangeFreq::Daily),
+            ("/archive", ChangeFreq::Never),
+            ("/home", ChangeFreq::Daily),
+        ] {
+            sitemap.add_entry(SiteMapData::new(
+                Url::parse("https://example.com")?.join(path)?,
+                "2024-10-08",
+                changefreq,
+            ))?;
+        }
+        sitemap.entries[2].priority = Some(0.3);
+
+        sitemap.assign_priority_from_changefreq();
+        let priorities: Vec<Option<f32>> = sitemap
+            .entries
+            .iter()
+            .map(|entry| entry.priority)
+            .collect();
+        assert_eq!(priorities, [Some(0.8), Some(0.1), Some(0.3)]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_entries_by_changefreq() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new();
+        for (loc, changefreq) in [
+            ("https://example.com/", ChangeFreq::Daily),
+            ("https://example.com/about", ChangeFreq::Yearly),
+            ("https://example.com/news", ChangeFreq::Daily),
+        ] {
+            sitemap.add_entry(SiteMapData {
+                loc: Url::parse(loc)?,
+                lastmod: "2024-10-08".to_string(),
+                changefreq,
+                priority: None,
+                extra: Vec::new(),
+                images: Vec::new(),
+                videos: Vec::new(),
+                alternates: Vec::new(),
+            })?;
+        }
+
+        assert_eq!(
+            sitemap.entries_by_changefreq(ChangeFreq::Daily).count(),
+            2
+        );
+        assert_eq!(
+            sitemap.entries_by_changefreq(ChangeFreq::Hourly).count(),
+            0
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_preserving_lastmod() -> SitemapResult<()> {
+        let mut previous = Sitemap::new();
+        previous.add_entry(SiteMapData {
+            loc: Url::parse("https://example.com/about")?,
+            lastmod: "2023-01-15".to_string(),
+            changefreq: ChangeFreq::Monthly,
+            priority: None,
+            extra: Vec::new(),
+            images: Vec::new(),
+            videos: Vec::new(),
+            alternates: Vec::new(),
+        })?;
+
+        let mut current = Sitemap::new();
+        current.add_url(Url::parse("https://example.com/about")?)?;
+        current.add_url(Url::parse("https://example.com/new")?)?;
+        let today = current.entries[1].lastmod.clone();
+
+        current.merge_preserving_lastmod(&previous);
+        assert_eq!(current.entries[0].lastmod, "2023-01-15");
+        assert_eq!(current.entries[1].lastmod, today);
+        Ok(())
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_write_gzip_to_matches_to_xml() -> SitemapResult<()> {
+        use std::io::Read;
+
+        let mut sitemap = Sitemap::new();
+        sitemap.add_url(Url::parse("https://example.com/")?)?;
+        sitemap.add_url(Url::parse("https://example.com/?a=1&b=2")?)?;
+
+        let mut compressed = Vec::new();
+        sitemap.write_gzip_to(&mut compressed)?;
+
+        let mut xml = String::new();
+        let _ = flate2::read::GzDecoder::new(compressed.as_slice())
+            .read_to_string(&mut xml)?;
+        assert_eq!(xml, sitemap.to_xml()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_serialize_into_reuses_buffer() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new();
+        sitemap.add_url(Url::parse("https://example.com/")?)?;
+        sitemap.add_url(Url::parse("https://example.com/about")?)?;
+
+        let mut buf = "stale contents".to_string();
+        sitemap.serialize_into(&mut buf)?;
+        assert_eq!(buf, sitemap.to_xml()?);
+
+        let _ =
+            sitemap.remove(&Url::parse("https://example.com/about")?);
+        let capacity = buf.capacity();
+        sitemap.serialize_into(&mut buf)?;
+        assert_eq!(buf, sitemap.to_xml()?);
+        assert_eq!(buf.capacity(), capacity);
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_to_matches_to_xml() -> SitemapResult<()> {
+        use std::io::{Read, Seek};
+
+        let mut sitemap = Sitemap::new();
+        sitemap.add_url(Url::parse("https://example.com/")?)?;
+        sitemap.add_url(Url::parse("https://example.com/?a=1&b=2")?)?;
+
+        let mut file = tempfile::tempfile()?;
+        sitemap.write_to(&mut file)?;
+        let mut xml = String::new();
+        let _ = file.seek(std::io::SeekFrom::Start(0))?;
+        let _ = file.read_to_string(&mut xml)?;
+        assert_eq!(xml, sitemap.to_xml()?);
+
+        let latin1 = sitemap.clone().with_encoding("ISO-8859-1")?;
+        let mut bytes = Vec::new();
+        latin1.write_to(&mut bytes)?;
+        assert_eq!(bytes, latin1.to_xml_bytes()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_trailing_slash_report() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new();
+        for loc in [
+            "https://example.com/",
+            "https://example.com/blog/",
+            "https://example.com/docs/",
+            "https://example.com/about",
+            "https://example.com/report.pdf",
+        ] {
+            sitemap.add_url(Url::parse(loc)?)?;
+        }
+
+        assert_eq!(sitemap.trailing_slash_report(), (2, 1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_into_iter() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new();
+        sitemap.add_url(Url::parse("https://example.com/a")?)?;
+        sitemap.add_url(Url::parse("https://example.com/b")?)?;
+
+        assert_eq!((&sitemap).into_iter().count(), 2);
+        let entries: Vec<SiteMapData> = sitemap.into_iter().collect();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].loc.as_str(), "https://example.com/b");
+        Ok(())
+    }
+
+    #[test]
+    fn test_append_sitemap_index_children() -> SitemapResult<()> {
+        let dir = tempfile::tempdir()?;
+        fs::create_dir(dir.path().join("sitemaps"))?;
+        for (name, loc) in [
+            ("blog.xml", "https://example.com/blog/"),
+            ("docs.xml", "https://example.com/docs/"),
+        ] {
+            let mut child = Sitemap::new();
+            child.add_url(Url::parse(loc)?)?;
+            fs::write(
+                dir.path().join("sitemaps").join(name),
+                child.to_xml()?,
+            )?;
+        }
+        let index = SitemapIndex::from_xml(
+            r#"<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+  <sitemap><loc>https://example.com/sitemaps/blog.xml</loc></sitemap>
+  <sitemap><loc>https://example.com/sitemaps/docs.xml</loc></sitemap>
+</sitemapindex>"#,
+        )?;
+
+        let mut sitemap = Sitemap::new();
+        assert_eq!(
+            sitemap
+                .append_sitemap_index_children(&index, dir.path())?,
+            2
+        );
+        let locs: Vec<&str> =
+            sitemap.iter().map(|entry| entry.loc.as_str()).collect();
+        assert_eq!(
+            locs,
+            ["https://example.com/blog/", "https://example.com/docs/"]
+        );
+
+        fs::remove_file(dir.path().join("sitemaps/docs.xml"))?;
+        match Sitemap::new()
+            .append_sitemap_index_children(&index, dir.path())
+        {
+            Err(SitemapError::IoError(e)) => {
+                assert!(e.to_string().contains("sitemaps/docs.xml"));
+            }
+            other => panic!("Expected IoError, got {:?}", other),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_strict_lastmod_reports_entry_index() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new().with_strict_lastmod(true);
+        for (loc, lastmod) in [
+            ("https://example.com/", "2024-10-08"),
+            ("https://example.com/a", "2024-10-08T10:00:00Z"),
+            ("https://example.com/b", "08/10/2024"),
+        ] {
+            sitemap.add_entry(SiteMapData {
+                loc: Url::parse(loc)?,
+                lastmod: lastmod.to_string(),
+                changefreq: ChangeFreq::Weekly,
+                priority: None,
+                extra: Vec::new(),
+                images: Vec::new(),
+                videos: Vec::new(),
+                alternates: Vec::new(),
+            })?;
+        }
+
+        match sitemap.to_xml() {
+            Err(SitemapError::InvalidLastmod {
+                index,
+                url,
+                lastmod,
+            }) => {
+                assert_eq!(index, 2);
+                assert_eq!(url, "https://example.com/b");
+                assert_eq!(lastmod, "08/10/2024");
+            }
+            other => panic!("Expected InvalidLastmod, got {:?}", other),
+        }
+        assert!(sitemap.with_strict_lastmod(false).to_xml().is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_pretty_print_indents_entries() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new().with_pretty_print(4);
+        sitemap.add_entry(SiteMapData {
+            loc: Url::parse("https://example.com/")?,
+            lastmod: "2024-10-08".to_string(),
+            changefreq: ChangeFreq::Daily,
+            priority: None,
+            extra: Vec::new(),
+            images: Vec::new(),
+            videos: Vec::new(),
+            alternates: Vec::new(),
+        })?;
+
+        let xml = sitemap.to_xml()?;
+        assert!(xml.contains("\n    <url>\n"));
+        assert!(
+            xml.contains("\n        <loc>https://example.com/</loc>")
+        );
+        assert_eq!(Sitemap::from_xml(&xml)?.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_by_prefix() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new();
+        for path in ["/", "/drafts/a", "/blog/post", "/drafts/b/c"] {
+            sitemap.add_entry(SiteMapData {
+                loc: Url::parse("https://example.com")?.join(path)?,
+                lastmod: "2024-10-08".to_string(),
+                changefreq: ChangeFreq::Weekly,
+                priority: None,
+                extra: Vec::new(),
+                images: Vec::new(),
+                videos: Vec::new(),
+                alternates: Vec::new(),
+            })?;
+        }
+
+        assert_eq!(sitemap.remove_by_prefix("/drafts"), 2);
+        let paths: Vec<&str> =
+            sitemap.iter().map(|entry| entry.loc.path()).collect();
+        assert_eq!(paths, ["/", "/blog/post"]);
+        assert_eq!(sitemap.remove_by_prefix("/drafts"), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_remove_retain_and_clear() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new();
+        for path in ["/", "/gone", "/blog/post", "/gone", "/about"] {
+            sitemap.push(SiteMapData::new(
+                Url::parse("https://example.com")?.join(path)?,
+                "2024-10-08",
+                ChangeFreq::Weekly,
+            ));
+        }
+
+        let gone = Url::parse("https://example.com/gone")?;
+        assert!(sitemap.remove(&gone));
+        assert!(!sitemap.remove(&gone));
+        assert_eq!(sitemap.len(), 3);
+
+        sitemap.retain(|entry| !entry.loc.path().starts_with("/blog"));
+        let paths: Vec<&str> =
+            sitemap.iter_urls().map(Url::path).collect();
+        assert_eq!(paths, ["/", "/about"]);
+
+        sitemap.clear();
+        assert!(sitemap.is_empty());
+        Ok(())
+    }
 
-        writer.write(XmlEvent::start_element("urlset").default_ns(
-            "http://www.sitemaps.org/schemas/sitemap/0.9",
-        ))?;
+    #[test]
+    fn test_with_dedup_rejects_duplicates() -> SitemapResult<()> {
+        let entry = |path: &str| -> SitemapResult<SiteMapData> {
+            Ok(SiteMapData::new(
+                Url::parse("https://example.com")?.join(path)?,
+                "2024-10-08",
+                ChangeFreq::Weekly,
+            ))
+        };
+        let mut sitemap = Sitemap::new();
+        sitemap.add_entry(entry("/a")?)?;
+        let mut sitemap = sitemap.with_dedup();
 
-        for entry in &self.entries {
-            // Start the <url> element
-            writer.write(XmlEvent::start_element("url"))?;
-
-            // <loc> entry
-            writer.write(XmlEvent::start_element("loc"))?;
-            writer.write(XmlEvent::characters(entry.loc.as_ref()))?;
-            writer.write(XmlEvent::end_element())?;
-
-            // <lastmod> entry
-            writer.write(XmlEvent::start_element("lastmod"))?;
-            writer.write(XmlEvent::characters(&entry.lastmod))?;
-            writer.write(XmlEvent::end_element())?;
-
-            // <changefreq> entry
-            writer.write(XmlEvent::start_element("changefreq"))?;
-            writer.write(XmlEvent::characters(
-                entry.changefreq.as_str(),
-            ))?;
-            writer.write(XmlEvent::end_element())?;
+        assert!(matches!(
+            sitemap.add_entry(entry("/a")?),
+            Err(SitemapError::DuplicateUrl(ref url))
+                if url == "https://example.com/a"
+        ));
+        sitemap.add_entry(entry("/b")?)?;
+        assert_eq!(sitemap.len(), 2);
 
-            // End the <url> element
-            writer.write(XmlEvent::end_element())?;
+        assert!(sitemap.remove(&entry("/a")?.loc));
+        sitemap.add_entry(entry("/a")?)?;
+        for shard in sitemap.split(1) {
+            assert!(shard.clone().add_entry(entry("/c")?).is_ok());
+            let existing = shard.iter().next().unwrap().clone();
+            assert!(shard.clone().add_entry(existing).is_err());
         }
 
-        // Close the <urlset> element
-        writer.write(XmlEvent::end_element())?;
+        let mut plain = Sitemap::new();
+        plain.add_entry(entry("/a")?)?;
+        plain.add_entry(entry("/a")?)?;
+        assert_eq!(plain.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_sorting() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new();
+        for (path, lastmod) in [
+            ("/c", "2024-10-08"),
+            ("/a", "not a date"),
+            ("/d", "2024-10-07T20:00:00Z"),
+            ("/b", "2023-01-15"),
+            ("/e", "garbage"),
+        ] {
+            sitemap.push(SiteMapData::new(
+                Url::parse("https://example.com")?.join(path)?,
+                lastmod,
+                ChangeFreq::Weekly,
+            ));
+        }
+        let paths = |sitemap: &Sitemap| -> Vec<String> {
+            sitemap
+                .iter_urls()
+                .map(|url| url.path().to_string())
+                .collect()
+        };
+
+        sitemap.sort_by_loc();
+        assert_eq!(paths(&sitemap), ["/a", "/b", "/c", "/d", "/e"]);
 
-        // Convert the output Vec<u8> directly into a string without intermediate allocations
-        let xml = unsafe { String::from_utf8_unchecked(output) };
+        sitemap.sort_by_lastmod();
+        assert_eq!(paths(&sitemap), ["/b", "/d", "/c", "/e", "/a"]);
 
-        // Check size before returning to ensure the sitemap isn't too large
-        if xml.len() > 10 * 1024 * 1024 {
-            return Err(SitemapError::SitemapTooLarge);
+        sitemap.sort_by(|a, b| b.loc.path().cmp(a.loc.path()));
+        assert_eq!(paths(&sitemap), ["/e", "/d", "/c", "/b", "/a"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_normalize_all() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new();
+        for loc in [
+            "https://example.com",
+            "https://example.com/about#team",
+            "https://example.com/about?utm_source=feed",
+            "ftp://example.com/file",
+            "https://example.com/blog",
+        ] {
+            sitemap.push(SiteMapData::new(
+                Url::parse(loc)?,
+                "2024-10-08",
+                ChangeFreq::Weekly,
+            ));
         }
 
-        Ok(xml)
+        let removed = sitemap
+            .normalize_all(&crate::utils::NormalizeOptions::default());
+        assert_eq!(removed, 2);
+        let locs: Vec<&str> =
+            sitemap.iter_urls().map(Url::as_str).collect();
+        assert_eq!(
+            locs,
+            [
+                "https://example.com/",
+                "https://example.com/about",
+                "https://example.com/blog"
+            ]
+        );
+        Ok(())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use dtt::dtt_now;
+    #[test]
+    fn test_with_encoding_latin1() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new().with_encoding("ISO-8859-1")?;
+        sitemap.add_entry(SiteMapData {
+            loc: Url::parse("https://example.com/")?,
+            lastmod: "2024-10-08".to_string(),
+            changefreq: ChangeFreq::Daily,
+            priority: None,
+            extra: Vec::new(),
+            images: Vec::new(),
+            videos: Vec::new(),
+            alternates: Vec::new(),
+        })?;
+
+        let bytes = sitemap.to_xml_bytes()?;
+        assert!(bytes.starts_with(
+            b"<?xml version=\"1.0\" encoding=\"ISO-8859-1\"?>"
+        ));
+        assert_eq!(bytes, sitemap.to_xml()?.into_bytes());
+
+        sitemap.with_changefreq_renderer(|_| {
+            "t\u{e4}glich \u{2713}".into()
+        });
+        assert!(matches!(
+            sitemap.to_xml_bytes(),
+            Err(SitemapError::UnencodableCharacter {
+                character: '\u{2713}',
+                encoding: "ISO-8859-1",
+            })
+        ));
+        assert!(Sitemap::new().with_encoding("UTF-16").is_err());
+        Ok(())
+    }
 
+    #[cfg(not(feature = "image-sitemap"))]
     #[test]
-    fn test_create_site_map_data() -> SitemapResult<()> {
-        let mut metadata = HashMap::new();
-        let _ = metadata.insert(
-            "last_build_date".to_string(),
-            "20 May 2023".to_string(),
+    fn test_images_are_ignored_without_feature() -> SitemapResult<()> {
+        let mut entry = SiteMapData::new(
+            Url::parse("https://x.com/gallery")?,
+            "2024-10-08",
+            ChangeFreq::Weekly,
         );
-        let _ = metadata
-            .insert("changefreq".to_string(), "weekly".to_string());
-        let _ = metadata.insert(
-            "permalink".to_string(),
-            "https://example.com".to_string(),
+        entry.images =
+            vec![ImageEntry::new(Url::parse("https://x.com/a.jpg")?)];
+        let mut sitemap = Sitemap::new();
+        sitemap.add_entry(entry)?;
+
+        let xml = sitemap.to_xml()?;
+        assert!(!xml.contains("image"));
+        assert_eq!(Sitemap::from_xml(&xml)?.len(), 1);
+        Ok(())
+    }
+
+    #[cfg(feature = "image-sitemap")]
+    #[test]
+    fn test_validate_flags_too_many_images() -> SitemapResult<()> {
+        let image = ImageEntry::new(Url::parse("https://x.com/a.jpg")?);
+        let mut entry = SiteMapData::new(
+            Url::parse("https://x.com/gallery")?,
+            "2024-10-08",
+            ChangeFreq::Weekly,
         );
+        entry.images = vec![image; 1_001];
+        let mut sitemap = Sitemap::new();
+        sitemap.add_url(Url::parse("https://x.com/")?)?;
+        sitemap.add_entry(entry)?;
 
-        let site_map_data = create_site_map_data(&metadata)?;
+        let location = Url::parse("https://x.com/sitemap.xml")?;
+        assert_eq!(
+            sitemap.validate(&location),
+            [ValidationIssue::TooManyImages {
+                index: 1,
+                count: 1_001
+            }]
+        );
+        let sitemap = sitemap.with_extension_limits(ExtensionLimits {
+            max_images: 2_000,
+        });
+        assert!(sitemap.validate(&location).is_empty());
+        Ok(())
+    }
 
-        assert_eq!(site_map_data.lastmod, "2023-05-20");
-        assert_eq!(site_map_data.changefreq, ChangeFreq::Weekly);
+    #[test]
+    fn test_validate_flags_out_of_scope_urls() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new();
+        for loc in [
+            "https://x.com/sub/",
+            "https://x.com/sub/page",
+            "https://x.com/other",
+            "https://y.com/sub/page",
+        ] {
+            sitemap.add_entry(SiteMapData {
+                loc: Url::parse(loc)?,
+                lastmod: "2024-10-08".to_string(),
+                changefreq: ChangeFreq::Weekly,
+                priority: None,
+                extra: Vec::new(),
+                images: Vec::new(),
+                videos: Vec::new(),
+                alternates: Vec::new(),
+            })?;
+        }
+
+        let location = Url::parse("https://x.com/sub/sitemap.xml")?;
         assert_eq!(
-            site_map_data.loc,
-            Url::parse("https://example.com")?
+            sitemap.validate(&location),
+            [
+                ValidationIssue::OutOfScope(
+                    "https://x.com/other".to_string()
+                ),
+                ValidationIssue::OutOfScope(
+                    "https://y.com/sub/page".to_string()
+                ),
+            ]
         );
+        let root = Url::parse("https://x.com/sitemap.xml")?;
+        assert_eq!(sitemap.validate(&root).len(), 1);
+
+        assert!(Sitemap::new()
+            .validate(&Url::parse("mailto:")?)
+            .is_empty());
+        for location in ["mailto:", "urn:isbn"] {
+            let issues = sitemap.validate(&Url::parse(location)?);
+            assert_eq!(issues.len(), 4);
+            assert!(issues.iter().all(|issue| matches!(
+                issue,
+                ValidationIssue::OutOfScope(_)
+            )));
+        }
         Ok(())
     }
 
     #[test]
-    fn test_convert_date_format() {
-        assert_eq!(convert_date_format("20 May 2023"), "2023-05-20");
-        assert_eq!(convert_date_format("2023-05-20"), "2023-05-20");
-        assert_eq!(convert_date_format("Invalid Date"), "Invalid Date");
+    fn test_from_xml_rejects_malformed_documents() {
+        let valid = Sitemap::new().to_xml().unwrap();
+        let nested = format!(
+            "<urlset xmlns=\"{}\"><url>{}{}</url></urlset>",
+            SITEMAP_NS,
+            "<a>".repeat(1_000),
+            "</a>".repeat(1_000)
+        );
+        let huge_attribute = format!(
+            "<urlset xmlns=\"{}\" data=\"{}\"><url>",
+            SITEMAP_NS,
+            "x".repeat(100_000)
+        );
+        let malformed = [
+            "",
+            "not xml at all",
+            &valid[..valid.len() - 3],
+            "<urlset><url><loc>https://example.com/</loc>",
+            "<urlset><url><loc>https://example.com/</url></urlset>",
+            "<urlset><url><loc>&bogus;</loc></url></urlset>",
+            "<urlset><url><loc>not a url</loc></url></urlset>",
+            "<urlset><url><lastmod>2024-10-08</lastmod></url></urlset>",
+            "<sitemapindex></sitemapindex>",
+            &huge_attribute,
+            &nested,
+        ];
+        for xml in malformed {
+            assert!(
+                Sitemap::from_xml(xml).is_err(),
+                "accepted {:?}",
+                xml
+            );
+        }
     }
 
     #[test]
-    fn test_sitemap_to_xml() -> SitemapResult<()> {
+    fn test_from_xml_bytes_rejects_invalid_utf8() {
+        let mut xml =
+            b"<urlset><url><loc>https://example.com/".to_vec();
+        xml.extend_from_slice(&[0xff, 0xfe]);
+        xml.extend_from_slice(b"</loc></url></urlset>");
+        assert!(Sitemap::from_xml_bytes(&xml).is_err());
+
+        let valid = b"<urlset><url><loc>https://example.com/</loc></url></urlset>";
+        assert_eq!(
+            Sitemap::from_xml_bytes(valid).map(|s| s.len()).ok(),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn test_merge_all_dedups_entries() -> SitemapResult<()> {
+        let mut sitemaps = Vec::new();
+        for paths in [["a", "b"], ["b", "c"], ["c", "d"], ["a", "e"]] {
+            let mut sitemap = Sitemap::new();
+            for path in paths {
+                sitemap.add_url(Url::parse(&format!(
+                    "https://example.com/{}",
+                    path
+                ))?)?;
+            }
+            sitemaps.push(sitemap);
+        }
+
+        let merged = Sitemap::merge_all(sitemaps)?;
+        let paths: Vec<&str> =
+            merged.iter().map(|entry| entry.loc.path()).collect();
+        assert_eq!(paths, ["/a", "/b", "/c", "/d", "/e"]);
+        assert!(Sitemap::merge_all(Vec::new())?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_all_reports_total_overflow() {
+        let halves = (0..2).map(|half| {
+            let mut sitemap = Sitemap::new();
+            for i in 0..30_000 {
+                sitemap.entries.push(SiteMapData {
+                    loc: Url::parse(&format!(
+                        "https://example.com/{}/{}",
+                        half, i
+                    ))
+                    .unwrap(),
+                    lastmod: "2024-10-08".to_string(),
+                    changefreq: ChangeFreq::Weekly,
+                    priority: None,
+                    extra: Vec::new(),
+                    images: Vec::new(),
+                    videos: Vec::new(),
+                    alternates: Vec::new(),
+                });
+            }
+            sitemap
+        });
+
+        assert!(matches!(
+            Sitemap::merge_all(halves),
+            Err(SitemapError::MaxUrlLimitExceeded(60_000))
+        ));
+    }
+
+    #[test]
+    fn test_case_collision_report() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new();
+        for loc in [
+            "https://example.com/About",
+            "https://EXAMPLE.com/contact",
+            "https://example.com/about",
+            "https://example.com/Contact",
+            "https://example.com/About",
+        ] {
+            sitemap.add_entry(SiteMapData {
+                loc: Url::parse(loc)?,
+                lastmod: "2024-10-08".to_string(),
+                changefreq: ChangeFreq::Weekly,
+                priority: None,
+                extra: Vec::new(),
+                images: Vec::new(),
+                videos: Vec::new(),
+                alternates: Vec::new(),
+            })?;
+        }
+
+        let report = sitemap.case_collision_report();
+        let pairs: Vec<(&str, &str)> =
+            report.iter().map(|(a, b)| (a.path(), b.path())).collect();
+        assert_eq!(
+            pairs,
+            [("/About", "/about"), ("/contact", "/Contact")]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_priority_is_written_and_parsed() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new();
+        sitemap.add_entry(
+            SiteMapData::new(
+                Url::parse("https://example.com/")?,
+                "2024-10-08",
+                ChangeFreq::Daily,
+            )
+            .with_priority(0.8)?,
+        )?;
+        sitemap.add_entry(
+            SiteMapData::new(
+                Url::parse("https://example.com/about")?,
+                "2024-10-08",
+                ChangeFreq::Daily,
+            )
+            .with_priority(1.0)?,
+        )?;
+
+        let xml = sitemap.to_xml()?;
+        assert!(xml.contains(
+            "<changefreq>daily</changefreq><priority>0.8</priority></url>"
+        ));
+        assert!(xml.contains("<priority>1.0</priority>"));
+        let parsed = Sitemap::from_xml(&xml)?;
+        assert_eq!(parsed.entries[0].priority, Some(0.8));
+        assert_eq!(parsed.entries[1].priority, Some(1.0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_default_priority_is_omitted() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new();
+        for (path, priority) in [("/", 0.5), ("/about", 0.6)] {
+            sitemap.add_entry(
+                SiteMapData::new(
+                    Url::parse("https://example.com")?.join(path)?,
+                    "2024-10-08",
+                    ChangeFreq::Daily,
+                )
+                .with_priority(priority)?,
+            )?;
+        }
+
+        let xml = sitemap.to_xml()?;
+        assert!(!xml.contains("<priority>0.5</priority>"));
+        assert!(xml.contains("<priority>0.6</priority>"));
+        assert!(sitemap
+            .with_omit_default_priority(false)
+            .to_xml()?
+            .contains("<priority>0.5</priority>"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_priority_out_of_range() -> SitemapResult<()> {
+        let entry = SiteMapData::new(
+            Url::parse("https://example.com/")?,
+            "2024-10-08",
+            ChangeFreq::Daily,
+        );
+        assert!(matches!(
+            entry.clone().with_priority(1.5),
+            Err(SitemapError::InvalidPriority(p)) if p == 1.5
+        ));
+
         let mut sitemap = Sitemap::new();
         sitemap.add_entry(SiteMapData {
-            loc: Url::parse("https://example.com")?,
-            lastmod: "2023-05-20".to_string(),
-            changefreq: ChangeFreq::Weekly,
+            priority: Some(-0.1),
+            ..entry
         })?;
+        assert!(matches!(
+            sitemap.to_xml(),
+            Err(SitemapError::InvalidPriority(_))
+        ));
+        let parsed = Sitemap::from_xml(
+            "<urlset><url><loc>https://example.com/</loc><priority>2</priority></url></urlset>"
+        )?;
+        assert_eq!(parsed.entries[0].priority, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_into_chunks() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new().with_cdata_loc(true);
+        for i in 0..5 {
+            sitemap.add_url(Url::parse(&format!(
+                "https://example.com/{}",
+                i
+            ))?)?;
+        }
+
+        let chunks = sitemap.split(2);
+        let sizes: Vec<usize> =
+            chunks.iter().map(Sitemap::len).collect();
+        assert_eq!(sizes, [2, 2, 1]);
+        assert_eq!(chunks[2].entries[0].loc.path(), "/4");
+        assert!(chunks[0].to_xml()?.contains("<![CDATA["));
+
+        assert_eq!(sitemap.split(0).len(), 5);
+        assert!(Sitemap::new().split(10).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_document_lastmod_comment() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new().with_document_lastmod(true);
+        for (path, lastmod) in [
+            ("/a", "2024-10-08"),
+            ("/b", "2024-11-02T09:30:00Z"),
+            ("/c", "not a date"),
+            ("/d", "2023-01-15"),
+        ] {
+            sitemap.add_entry(SiteMapData::new(
+                Url::parse("https://example.com")?.join(path)?,
+                lastmod,
+                ChangeFreq::Weekly,
+            ))?;
+        }
 
         let xml = sitemap.to_xml()?;
-        assert!(xml.contains("<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">"));
-        assert!(xml.contains("<url>"));
-        assert!(xml.contains("<loc>https://example.com/</loc>"));
-        assert!(xml.contains("<lastmod>2023-05-20</lastmod>"));
-        assert!(xml.contains("<changefreq>weekly</changefreq>"));
+        assert!(xml.contains(
+            "\"><!-- lastmod: 2024-11-02 --><url><loc>https://example.com/a"
+        ));
+        assert_eq!(Sitemap::from_xml(&xml)?.len(), 4);
+        assert!(!sitemap
+            .with_document_lastmod(false)
+            .to_xml()?
+            .contains("<!--"));
         Ok(())
     }
 
     #[test]
-    fn test_sitemap_size_limit() -> SitemapResult<()> {
+    fn test_section_comments() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new().with_section_comments(1);
+        for path in ["/blog/b", "/about", "/blog/a", "/"] {
+            sitemap.add_entry(SiteMapData::new(
+                Url::parse("https://example.com")?.join(path)?,
+                "2024-10-08",
+                ChangeFreq::Weekly,
+            ))?;
+        }
+
+        let xml = sitemap.to_xml()?;
+        let position = |needle: &str| xml.find(needle).unwrap();
+        assert_eq!(xml.matches("<!-- /blog -->").count(), 1);
+        assert!(position("<!-- / -->") < position("<!-- /about -->"));
+        assert!(
+            position("example.com/about<") < position("<!-- /blog -->")
+        );
+        assert!(
+            position("<!-- /blog -->")
+                < position("example.com/blog/a<")
+        );
+        assert!(
+            position("example.com/blog/a<")
+                < position("example.com/blog/b<")
+        );
+        assert_eq!(Sitemap::from_xml(&xml)?.len(), 4);
+        assert!(!Sitemap::new().to_xml()?.contains("<!--"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_extra_elements_are_written() -> SitemapResult<()> {
         let mut sitemap = Sitemap::new();
-        for i in 0..50_000 {
+        sitemap.add_entry(
+            SiteMapData::new(
+                Url::parse("https://example.com/")?,
+                "2024-10-08",
+                ChangeFreq::Daily,
+            )
+            .with_extra_ns("mobile", MOBILE_NS, "mobile", "")?
+            .with_extra("note", "a & b")?,
+        )?;
+
+        let xml = sitemap.to_xml()?;
+        assert!(
+            xml.contains(&format!("xmlns:mobile=\"{}\"", MOBILE_NS))
+        );
+        assert!(xml.contains(
+            "<changefreq>daily</changefreq><mobile:mobile /><note>a &amp; b</note></url>"
+        ));
+        assert_eq!(Sitemap::from_xml(&xml)?.len(), 1);
+
+        let mut streamed = Vec::new();
+        let mut writer = SitemapWriter::new(&mut streamed);
+        writer.start()?;
+        writer.write_entry(&sitemap.entries[0])?;
+        let _ = writer.finish()?;
+        let streamed = String::from_utf8(streamed)?;
+        assert!(streamed.contains(&format!(
+            "<mobile:mobile xmlns:mobile=\"{}\" />",
+            MOBILE_NS
+        )));
+        assert_eq!(Sitemap::from_xml(&streamed)?.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_extra_element_names_are_validated() -> SitemapResult<()> {
+        let entry = SiteMapData::new(
+            Url::parse("https://example.com/")?,
+            "2024-10-08",
+            ChangeFreq::Daily,
+        );
+        for name in ["", "1st", "a b", "a:b", "a:b:c", "<x>", ":x"] {
+            assert!(
+                entry.clone().with_extra(name, "v").is_err(),
+                "{}",
+                name
+            );
+        }
+        for (prefix, namespace, name) in [
+            ("mobile", "", "mobile"),
+            ("xmlns", MOBILE_NS, "mobile"),
+            ("1x", MOBILE_NS, "mobile"),
+            ("mobile", MOBILE_NS, "a:b"),
+        ] {
+            assert!(entry
+                .clone()
+                .with_extra_ns(prefix, namespace, name, "v")
+                .is_err());
+        }
+
+        for extra in [
+            ExtraElement {
+                name: "bad name".to_string(),
+                value: String::new(),
+                namespace: None,
+            },
+            ExtraElement {
+                name: "mobile:mobile".to_string(),
+                value: String::new(),
+                namespace: None,
+            },
+            ExtraElement {
+                name: "xhtml:custom".to_string(),
+                value: String::new(),
+                namespace: Some(MOBILE_NS.to_string()),
+            },
+        ] {
+            let mut sitemap = Sitemap::new();
             sitemap.add_entry(SiteMapData {
-                loc: Url::parse(&format!("https://example.com/{}", i))?,
-                lastmod: "2023-05-20".to_string(),
-                changefreq: ChangeFreq::Weekly,
+                extra: vec![extra],
+                ..entry.clone()
             })?;
+            assert!(sitemap.to_xml().is_err());
         }
-        assert!(matches!(
-            sitemap.add_entry(SiteMapData {
-                loc: Url::parse("https://example.com/toomany")?,
-                lastmod: "2023-05-20".to_string(),
-                changefreq: ChangeFreq::Weekly,
-            }),
-            Err(SitemapError::MaxUrlLimitExceeded(_))
+
+        let mut sitemap = Sitemap::new();
+        for (path, namespace) in
+            [("/a", MOBILE_NS), ("/b", "urn:other")]
+        {
+            sitemap.add_entry(
+                SiteMapData::new(
+                    Url::parse("https://example.com")?.join(path)?,
+                    "2024-10-08",
+                    ChangeFreq::Daily,
+                )
+                .with_extra_ns("mobile", namespace, "mobile", "")?,
+            )?;
+        }
+        assert!(sitemap.to_xml().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_push_appends_entry() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new();
+        sitemap.push(SiteMapData::new(
+            Url::parse("https://example.com/")?,
+            "2024-10-08",
+            ChangeFreq::Daily,
         ));
+        assert_eq!(sitemap.len(), 1);
+        sitemap.push(SiteMapData::new(
+            Url::parse("https://example.com/about")?,
+            "2024-10-08",
+            ChangeFreq::Daily,
+        ));
+        assert_eq!(sitemap.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_future_dated_entries() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new();
+        let today = DateTime::new().format("[year]-[month]-[day]")?;
+        for (path, lastmod) in [
+            ("/old", "2020-01-01"),
+            ("/today", today.as_str()),
+            ("/future", "2099-01-01"),
+            ("/broken", "soon"),
+        ] {
+            sitemap.add_entry(SiteMapData::new(
+                Url::parse("https://example.com")?.join(path)?,
+                lastmod,
+                ChangeFreq::Weekly,
+            ))?;
+        }
+
+        let day = std::time::Duration::from_secs(24 * 60 * 60);
+        let flagged: Vec<&str> = sitemap
+            .future_dated_entries(day)
+            .iter()
+            .map(|entry| entry.loc.path())
+            .collect();
+        assert_eq!(flagged, ["/future"]);
+        Ok(())
+    }
+
+    /// Reads `xml` to the end, returning the text of its unprefixed `<loc>`
+    /// elements.
+    fn parsed_locs(xml: &[u8]) -> Vec<String> {
+        let mut locs = Vec::new();
+        let mut in_loc = false;
+        for event in EventReader::new(xml) {
+            match event.expect("sitemap XML is not well-formed") {
+                ReaderEvent::StartElement { name, .. } => {
+                    in_loc = name.local_name == "loc"
+                        && name.prefix.is_none();
+                }
+                ReaderEvent::Characters(text)
+                | ReaderEvent::CData(text)
+                    if in_loc =>
+                {
+                    locs.push(text);
+                }
+                _ => in_loc = false,
+            }
+        }
+        locs
+    }
+
+    #[test]
+    fn test_special_characters_are_escaped() -> SitemapResult<()> {
+        let locs = [
+            "https://example.com/?a=1&b=2",
+            "https://example.com/<x>",
+            "https://example.com/?q=]]>&quote=\"'",
+        ];
+        let mut sitemap = Sitemap::new();
+        for loc in locs {
+            let entry = SiteMapData::new(
+                Url::parse(loc)?,
+                "2024-10-08",
+                ChangeFreq::Weekly,
+            )
+            .with_extra("note", "a < b && c > d")?;
+            #[cfg(feature = "image-sitemap")]
+            let entry = entry.with_image(ImageEntry {
+                caption: Some("Fish & <chips>".to_string()),
+                ..ImageEntry::new(Url::parse(loc)?)
+            })?;
+            sitemap.add_entry(entry)?;
+        }
+        let expected: Vec<String> =
+            sitemap.iter_urls().map(Url::to_string).collect();
+
+        for cdata_loc in [false, true] {
+            let xml =
+                sitemap.clone().with_cdata_loc(cdata_loc).to_xml()?;
+            // Only a CDATA section may hold a raw `&`.
+            assert_eq!(xml.contains("&b"), cdata_loc);
+            assert!(!xml.contains("& "));
+            assert_eq!(parsed_locs(xml.as_bytes()), expected);
+        }
+
+        let mut output = Vec::new();
+        let mut writer = SitemapWriter::new(&mut output);
+        writer.start()?;
+        for entry in sitemap.iter() {
+            writer.write_entry(entry)?;
+        }
+        writer.write_element("note", "<&>")?;
+        let _ = writer.finish()?;
+        assert_eq!(parsed_locs(&output), expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_xml_characters_are_rejected() -> SitemapResult<()> {
+        let entry = SiteMapData::new(
+            Url::parse("https://example.com/")?,
+            "2024-10-08",
+            ChangeFreq::Weekly,
+        );
+        let mut sitemap = Sitemap::new();
+        sitemap
+            .add_entry(entry.clone().with_extra("note", "a\u{1}b")?)?;
+        match sitemap.to_xml() {
+            Err(SitemapError::InvalidXmlCharacter {
+                character,
+                element,
+            }) => {
+                assert_eq!(character, '\u{1}');
+                assert_eq!(element, "note");
+            }
+            other => {
+                panic!("Expected InvalidXmlCharacter, got {:?}", other)
+            }
+        }
+
+        let mut sitemap = Sitemap::new();
+        sitemap.add_entry(SiteMapData {
+            lastmod: "2024-10-08\u{0}".to_string(),
+            ..entry.with_extra("note", "tab\tand\nnewline")?
+        })?;
+        assert!(sitemap.to_xml().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_hosts() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new();
+        for url in [
+            "https://b.example.com/",
+            "https://a.example.com/",
+            "https://b.example.com/about",
+        ] {
+            sitemap.add_url(Url::parse(url)?)?;
+        }
+
+        assert_eq!(
+            sitemap.hosts(),
+            vec!["b.example.com", "a.example.com"]
+        );
+        assert!(Sitemap::new().hosts().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_count_distinct_paths() -> SitemapResult<()> {
+        let mut sitemap = Sitemap::new();
+        for url in [
+            "https://example.com/search?a=1",
+            "https://example.com/search?a=2",
+            "http://www.example.com/search",
+            "https://example.com/about",
+        ] {
+            sitemap.add_url(Url::parse(url)?)?;
+        }
+
+        assert_eq!(sitemap.count_distinct_paths(), 2);
+        assert_eq!(Sitemap::new().count_distinct_paths(), 0);
         Ok(())
     }
 
@@ -0,0 +1,130 @@
+// src/sitemap/alternate.rs
+
+use crate::error::{SitemapError, SitemapResult};
+use std::io::Write;
+use url::Url;
+use xml::writer::{EventWriter, XmlEvent};
+
+/// The XHTML namespace, used for `<xhtml:link>` alternates.
+pub(crate) const XHTML_NS: &str = "http://www.w3.org/1999/xhtml";
+
+/// A localized version of a page, written as an
+/// `<xhtml:link rel="alternate">` element of the page's `<url>` entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alternate {
+    /// The language of the alternate, such as `de`, `en-GB` or
+    /// `x-default`.
+    pub hreflang: String,
+    /// The location of the alternate.
+    pub href: Url,
+}
+
+impl Alternate {
+    /// Creates an alternate, checking its language code.
+    ///
+    /// # Arguments
+    /// * `hreflang` - A language code in `xx` or `xx-YY` form, or
+    ///   `x-default`.
+    /// * `href` - The location of the alternate.
+    ///
+    /// # Returns
+    /// The alternate, or `SitemapError::InvalidHreflang` if the language
+    /// code is malformed.
+    pub fn new(
+        hreflang: impl Into<String>,
+        href: Url,
+    ) -> SitemapResult<Self> {
+        let hreflang = hreflang.into();
+        check_hreflang(&hreflang)?;
+        Ok(Alternate { hreflang, href })
+    }
+}
+
+/// Checks that `hreflang` is `x-default` or a two-letter language code,
+/// optionally followed by a two-letter region code.
+pub(crate) fn check_hreflang(hreflang: &str) -> SitemapResult<()> {
+    let is_code = |part: &str| {
+        part.len() == 2 && part.chars().all(|c| c.is_ascii_alphabetic())
+    };
+    let mut parts = hreflang.splitn(2, '-');
+    let language = parts.next().unwrap_or_default();
+    let valid = hreflang == "x-default"
+        || (is_code(language) && parts.next().map_or(true, is_code));
+    if valid {
+        Ok(())
+    } else {
+        Err(SitemapError::InvalidHreflang(hreflang.to_string()))
+    }
+}
+
+/// Writes `alternate` as an `<xhtml:link>` element.
+///
+/// When `declare_ns` is set, the element declares the `xhtml` prefix
+/// itself, for documents whose `<urlset>` was written without it.
+pub(crate) fn write_alternate<W: Write>(
+    writer: &mut EventWriter<W>,
+    alternate: &Alternate,
+    declare_ns: bool,
+) -> SitemapResult<()> {
+    check_hreflang(&alternate.hreflang)?;
+    let start = XmlEvent::start_element("xhtml:link")
+        .attr("rel", "alternate")
+        .attr("hreflang", &alternate.hreflang)
+        .attr("href", alternate.href.as_str());
+    if declare_ns {
+        writer.write(start.ns("xhtml", XHTML_NS))?;
+    } else {
+        writer.write(start)?;
+    }
+    writer.write(XmlEvent::end_element())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sitemap::{ChangeFreq, SiteMapData, Sitemap};
+
+    #[test]
+    fn test_alternates_are_written() -> SitemapResult<()> {
+        let entry = SiteMapData::new(
+            Url::parse("https://example.com/en/")?,
+            "2024-10-08",
+            ChangeFreq::Weekly,
+        )
+        .with_alternate(Alternate::new(
+            "de",
+            Url::parse("https://example.com/de/")?,
+        )?)?
+        .with_alternate(Alternate::new(
+            "x-default",
+            Url::parse("https://example.com/")?,
+        )?)?;
+        let mut sitemap = Sitemap::new();
+        sitemap.add_entry(entry)?;
+
+        let xml = sitemap.to_xml()?;
+        assert!(xml.contains(&format!("xmlns:xhtml=\"{}\"", XHTML_NS)));
+        assert!(xml.contains(
+            "<xhtml:link rel=\"alternate\" hreflang=\"de\" \
+             href=\"https://example.com/de/\" />"
+        ));
+        assert!(xml.contains("hreflang=\"x-default\""));
+        assert_eq!(Sitemap::from_xml(&xml)?.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_hreflang_validation() {
+        for valid in ["en", "en-GB", "pt-br", "x-default"] {
+            assert!(check_hreflang(valid).is_ok(), "{}", valid);
+        }
+        for invalid in ["", "e", "eng", "en-", "en-GBR", "en_GB", "x-y"]
+        {
+            assert!(matches!(
+                check_hreflang(invalid),
+                Err(SitemapError::InvalidHreflang(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,289 @@
+// src/sitemap/compact.rs
+
+use super::alternate::Alternate;
+use super::image::ImageEntry;
+use super::video::VideoEntry;
+use super::{
+    estimated_size, extension_namespaces, into_checked_xml,
+    lastmod_text, write_url, write_urlset_start, ChangeFreq,
+    EntryExtensions, ExtraElement, LastmodPrecision, SiteMapData,
+    Sitemap, MAX_URLS,
+};
+use crate::error::{SitemapError, SitemapResult};
+use url::Url;
+use xml::writer::{EventWriter, XmlEvent};
+
+/// A sitemap entry that stores its location as a plain string.
+///
+/// A `Url` keeps both the serialized URL and the offsets of its components,
+/// whereas a `CompactEntry` only keeps the string. The `Url` is re-parsed on
+/// demand with [`CompactEntry::url`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CompactEntry {
+    changefreq: ChangeFreq,
+    lastmod: Box<str>,
+    loc: Box<str>,
+    priority: Option<f32>,
+    extra: Vec<ExtraElement>,
+    images: Vec<ImageEntry>,
+    videos: Vec<VideoEntry>,
+    alternates: Vec<Alternate>,
+}
+
+impl CompactEntry {
+    /// Returns the extension elements of the entry.
+    fn extensions(&self) -> EntryExtensions<'_> {
+        EntryExtensions {
+            extra: &self.extra,
+            alternates: &self.alternates,
+            #[cfg(feature = "image-sitemap")]
+            images: &self.images,
+            videos: &self.videos,
+        }
+    }
+
+    /// Returns the location of the page.
+    pub fn loc(&self) -> &str {
+        &self.loc
+    }
+
+    /// Returns the last modification date of the page.
+    pub fn lastmod(&self) -> &str {
+        &self.lastmod
+    }
+
+    /// Returns the change frequency of the page.
+    pub fn changefreq(&self) -> ChangeFreq {
+        self.changefreq
+    }
+
+    /// Returns the priority of the page, if set.
+    pub fn priority(&self) -> Option<f32> {
+        self.priority
+    }
+
+    /// Returns the extension elements of the page.
+    pub fn extra(&self) -> &[ExtraElement] {
+        &self.extra
+    }
+
+    /// Returns the images on the page.
+    pub fn images(&self) -> &[ImageEntry] {
+        &self.images
+    }
+
+    /// Returns the videos on the page.
+    pub fn videos(&self) -> &[VideoEntry] {
+        &self.videos
+    }
+
+    /// Returns the localized alternates of the page.
+    pub fn alternates(&self) -> &[Alternate] {
+        &self.alternates
+    }
+
+    /// Parses the stored location back into a `Url`.
+    ///
+    /// # Returns
+    /// The parsed `Url`, or an error if the stored string is not a valid URL.
+    pub fn url(&self) -> SitemapResult<Url> {
+        Url::parse(&self.loc).map_err(SitemapError::UrlError)
+    }
+
+    /// Converts the entry back into a `SiteMapData`, re-parsing its location.
+    pub fn to_site_map_data(&self) -> SitemapResult<SiteMapData> {
+        Ok(SiteMapData {
+            changefreq: self.changefreq,
+            lastmod: self.lastmod.to_string(),
+            loc: self.url()?,
+            priority: self.priority,
+            extra: self.extra.clone(),
+            images: self.images.clone(),
+            videos: self.videos.clone(),
+            alternates: self.alternates.clone(),
+        })
+    }
+}
+
+impl From<SiteMapData> for CompactEntry {
+    fn from(entry: SiteMapData) -> Self {
+        CompactEntry {
+            changefreq: entry.changefreq,
+            lastmod: entry.lastmod.into_boxed_str(),
+            loc: String::from(entry.loc).into_boxed_str(),
+            priority: entry.priority,
+            extra: entry.extra,
+            images: entry.images,
+            videos: entry.videos,
+            alternates: entry.alternates,
+        }
+    }
+}
+
+/// A memory-efficient collection of sitemap entries.
+///
+/// `CompactSitemap` is intended for holding very large URL sets in memory,
+/// for example before splitting them into several sitemaps. Entries are
+/// stored as [`CompactEntry`] values and serialized straight from their
+/// strings, producing the same XML as [`Sitemap::to_xml`] with the default
+/// settings. Unlike `Sitemap`, adding entries is not limited to 50,000 URLs;
+/// the limit is only enforced when serializing.
+#[derive(Debug, Default, Clone)]
+pub struct CompactSitemap {
+    entries: Vec<CompactEntry>,
+}
+
+impl CompactSitemap {
+    /// Creates a new empty `CompactSitemap`.
+    pub fn new() -> Self {
+        CompactSitemap {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Adds an entry, dropping its parsed `Url` representation.
+    ///
+    /// # Arguments
+    /// * `entry` - The `SiteMapData` entry to add.
+    pub fn add_entry(&mut self, entry: SiteMapData) {
+        self.entries.push(entry.into());
+    }
+
+    /// Returns the current number of entries.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Checks if the sitemap is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns an iterator over the stored entries.
+    pub fn iter(&self) -> impl Iterator<Item = &CompactEntry> {
+        self.entries.iter()
+    }
+
+    /// Generates the XML representation of the sitemap.
+    ///
+    /// # Returns
+    /// A string containing the XML representation of the sitemap, or an error if
+    /// there are more than 50,000 entries or generation fails.
+    pub fn to_xml(&self) -> SitemapResult<String> {
+        if self.entries.len() > MAX_URLS {
+            return Err(SitemapError::MaxUrlLimitExceeded(
+                self.entries.len(),
+            ));
+        }
+
+        let mut output = Vec::with_capacity(estimated_size(self.len()));
+        let mut writer = EventWriter::new(&mut output);
+
+        let namespaces = extension_namespaces(
+            self.entries.iter().map(CompactEntry::extensions),
+        )?;
+        write_urlset_start(&mut writer, "UTF-8", &namespaces)?;
+        for entry in &self.entries {
+            write_url(
+                &mut writer,
+                &entry.loc,
+                &lastmod_text(
+                    &entry.lastmod,
+                    LastmodPrecision::DateOnly,
+                ),
+                Some(entry.changefreq.as_str()),
+                entry.priority,
+                entry.extensions(),
+                false,
+                false,
+            )?;
+        }
+        writer.write(XmlEvent::end_element())?;
+
+        into_checked_xml(output)
+    }
+
+    /// Converts the entries into a `Sitemap`, re-parsing every location.
+    ///
+    /// # Returns
+    /// The `Sitemap`, or an error if a location fails to parse or there are
+    /// more than 50,000 entries.
+    pub fn to_sitemap(&self) -> SitemapResult<Sitemap> {
+        let mut sitemap = Sitemap::with_capacity(self.len());
+        for entry in &self.entries {
+            sitemap.add_entry(entry.to_site_map_data()?)?;
+        }
+        Ok(sitemap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::mem::size_of;
+
+    fn entry(loc: &str, lastmod: &str) -> SiteMapData {
+        SiteMapData {
+            loc: Url::parse(loc).expect("Failed to parse URL"),
+            lastmod: lastmod.to_string(),
+            changefreq: ChangeFreq::Daily,
+            priority: None,
+            extra: Vec::new(),
+            images: Vec::new(),
+            videos: Vec::new(),
+            alternates: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_compact_xml_matches_sitemap() -> SitemapResult<()> {
+        let entries = vec![
+            entry("https://example.com", "2024-10-08"),
+            entry(
+                "https://example.com/?a=1&b=2",
+                "2024-10-08T10:00:00Z",
+            ),
+            entry("https://example.com/about", "2024-10-09"),
+        ];
+
+        let mut sitemap = Sitemap::new();
+        let mut compact = CompactSitemap::new();
+        for entry in entries {
+            sitemap.add_entry(entry.clone())?;
+            compact.add_entry(entry);
+        }
+
+        assert_eq!(compact.to_xml()?, sitemap.to_xml()?);
+        assert_eq!(compact.to_sitemap()?.to_xml()?, sitemap.to_xml()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_compact_entry_is_smaller() -> SitemapResult<()> {
+        assert!(size_of::<CompactEntry>() < size_of::<SiteMapData>());
+
+        let compact = CompactEntry::from(entry(
+            "https://example.com",
+            "2024-10-08",
+        ));
+        assert_eq!(compact.loc(), "https://example.com/");
+        assert_eq!(compact.url()?.host_str(), Some("example.com"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_compact_limit_enforced_on_serialization() {
+        let mut compact = CompactSitemap::new();
+        for i in 0..=MAX_URLS {
+            compact.add_entry(entry(
+                &format!("https://example.com/{}", i),
+                "2024-10-08",
+            ));
+        }
+        assert_eq!(compact.len(), MAX_URLS + 1);
+        assert!(matches!(
+            compact.to_xml(),
+            Err(SitemapError::MaxUrlLimitExceeded(_))
+        ));
+    }
+}
@@ -0,0 +1,149 @@
+// src/sitemap/image.rs
+
+#[cfg(feature = "image-sitemap")]
+use super::write_element;
+#[cfg(feature = "image-sitemap")]
+use crate::error::SitemapResult;
+#[cfg(feature = "image-sitemap")]
+use std::io::Write;
+use url::Url;
+#[cfg(feature = "image-sitemap")]
+use xml::writer::{EventWriter, XmlEvent};
+
+/// The XML namespace of Google's image sitemap extension.
+#[cfg(feature = "image-sitemap")]
+pub(crate) const IMAGE_NS: &str =
+    "http://www.google.com/schemas/sitemap-image/1.1";
+
+/// The maximum number of images Google accepts for a single URL.
+pub const MAX_IMAGES_PER_URL: usize = 1_000;
+
+/// An image on a page, written as an `<image:image>` element of the
+/// page's `<url>` entry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageEntry {
+    /// The location of the image.
+    pub loc: Url,
+    /// A caption for the image.
+    pub caption: Option<String>,
+    /// The title of the image.
+    pub title: Option<String>,
+    /// Where the image was taken, such as `Limerick, Ireland`.
+    pub geo_location: Option<String>,
+    /// The location of the image's license.
+    pub license: Option<Url>,
+}
+
+impl ImageEntry {
+    /// Creates an image entry with only a location.
+    ///
+    /// # Arguments
+    /// * `loc` - The location of the image.
+    pub fn new(loc: Url) -> Self {
+        ImageEntry {
+            loc,
+            caption: None,
+            title: None,
+            geo_location: None,
+            license: None,
+        }
+    }
+}
+
+/// Writes `image` as an `<image:image>` element.
+///
+/// When `declare_ns` is set, the element declares the `image` prefix
+/// itself, for documents whose `<urlset>` was written without it.
+#[cfg(feature = "image-sitemap")]
+pub(crate) fn write_image<W: Write>(
+    writer: &mut EventWriter<W>,
+    image: &ImageEntry,
+    declare_ns: bool,
+) -> SitemapResult<()> {
+    let start = XmlEvent::start_element("image:image");
+    if declare_ns {
+        writer.write(start.ns("image", IMAGE_NS))?;
+    } else {
+        writer.write(start)?;
+    }
+    write_element(writer, "image:loc", image.loc.as_str())?;
+    let optional = [
+        ("image:caption", image.caption.as_deref()),
+        ("image:title", image.title.as_deref()),
+        ("image:geo_location", image.geo_location.as_deref()),
+        ("image:license", image.license.as_ref().map(Url::as_str)),
+    ];
+    for (name, value) in optional {
+        if let Some(value) = value {
+            write_element(writer, name, value)?;
+        }
+    }
+    writer.write(XmlEvent::end_element())?;
+    Ok(())
+}
+
+#[cfg(all(test, feature = "image-sitemap"))]
+mod tests {
+    use super::*;
+    use crate::error::SitemapError;
+    use crate::sitemap::{ChangeFreq, SiteMapData, Sitemap};
+
+    fn page() -> SiteMapData {
+        SiteMapData::new(
+            Url::parse("https://example.com/gallery").unwrap(),
+            "2024-10-08",
+            ChangeFreq::Weekly,
+        )
+    }
+
+    #[test]
+    fn test_images_are_written() -> SitemapResult<()> {
+        let image = ImageEntry {
+            caption: Some("Sunset & sea".to_string()),
+            license: Some(Url::parse("https://example.com/license")?),
+            ..ImageEntry::new(Url::parse("https://example.com/a.jpg")?)
+        };
+        let mut sitemap = Sitemap::new();
+        sitemap.add_entry(page().with_image(image)?)?;
+        sitemap.add_entry(SiteMapData::new(
+            Url::parse("https://example.com/")?,
+            "2024-10-08",
+            ChangeFreq::Weekly,
+        ))?;
+
+        let xml = sitemap.to_xml()?;
+        assert!(xml.contains(&format!("xmlns:image=\"{}\"", IMAGE_NS)));
+        assert!(xml.contains(
+            "<image:image><image:loc>https://example.com/a.jpg</image:loc>\
+             <image:caption>Sunset &amp; sea</image:caption>\
+             <image:license>https://example.com/license</image:license>\
+             </image:image></url>"
+        ));
+        assert_eq!(Sitemap::from_xml(&xml)?.len(), 2);
+
+        let mut plain = Sitemap::new();
+        plain.add_entry(page())?;
+        assert!(!plain.to_xml()?.contains("xmlns:image"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_image_limit_per_url() -> SitemapResult<()> {
+        let image =
+            ImageEntry::new(Url::parse("https://example.com/a.jpg")?);
+        let mut entry = page();
+        for _ in 0..MAX_IMAGES_PER_URL {
+            entry = entry.with_image(image.clone())?;
+        }
+        assert!(matches!(
+            entry.clone().with_image(image.clone()),
+            Err(SitemapError::TooManyImages { count: 1_001, .. })
+        ));
+
+        entry.images.push(image);
+        let mut sitemap = Sitemap::new();
+        sitemap.add_entry(entry)?;
+        assert!(sitemap.to_xml().is_err());
+        Ok(())
+    }
+}
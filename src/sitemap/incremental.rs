@@ -0,0 +1,455 @@
+// src/sitemap/incremental.rs
+
+use super::writer::SitemapWriter;
+use super::{ByteCounter, SiteMapData, MAX_SITEMAP_SIZE, MAX_URLS};
+use crate::error::{SitemapError, SitemapResult};
+use crate::utils::shard_path;
+use log::warn;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use url::Url;
+
+/// The length of the closing `</urlset>` tag.
+const CLOSING_TAG_LEN: usize = "</urlset>".len();
+
+/// Writes a sitemap to disk one entry at a time.
+///
+/// Crawlers that discover URLs over time can stream them straight to the
+/// output file instead of holding the whole sitemap in memory. The header
+/// is written when the writer is created, each call to
+/// [`IncrementalSitemapWriter::add_entry`] appends a `<url>` element, and
+/// [`IncrementalSitemapWriter::finish`] closes the document.
+///
+/// The 50,000 URL and 10MB limits are tracked as entries are written. A
+/// writer created with [`IncrementalSitemapWriter::create`] rejects entries
+/// past either limit, while one created with
+/// [`IncrementalSitemapWriter::create_rolling`] starts a new shard instead.
+/// The byte limit can be lowered with
+/// [`IncrementalSitemapWriter::with_shard_size_limit`].
+///
+/// An entry too large to share a shard with others is written to a shard
+/// of its own with a warning, as long as it fits in 10MB alone; otherwise
+/// it's rejected with `SitemapError::UrlTooLarge`.
+///
+/// A writer dropped without calling `finish` leaves the current file
+/// without its closing tag.
+#[derive(Debug)]
+pub struct IncrementalSitemapWriter {
+    path: PathBuf,
+    rolling: bool,
+    writer: SitemapWriter<ByteCounter<BufWriter<File>>>,
+    shard_len: usize,
+    len: usize,
+    paths: Vec<PathBuf>,
+    header_len: usize,
+    size_limit: usize,
+}
+
+impl IncrementalSitemapWriter {
+    /// Creates a writer for a single sitemap at `path`.
+    ///
+    /// # Arguments
+    /// * `path` - The file to write the sitemap to.
+    ///
+    /// # Returns
+    /// The writer, or an error if the file cannot be created.
+    pub fn create<P: AsRef<Path>>(path: P) -> SitemapResult<Self> {
+        Self::open(path.as_ref().to_path_buf(), false)
+    }
+
+    /// Creates a writer that rolls over to a new shard when a limit is hit.
+    ///
+    /// Shards are named like the CLI's split output: `sitemap.xml` is
+    /// written as `sitemap-1.xml`, `sitemap-2.xml`, and so on.
+    ///
+    /// # Arguments
+    /// * `path` - The base path the shard names are derived from.
+    ///
+    /// # Returns
+    /// The writer, or an error if the first shard cannot be created.
+    pub fn create_rolling<P: AsRef<Path>>(
+        path: P,
+    ) -> SitemapResult<Self> {
+        Self::open(path.as_ref().to_path_buf(), true)
+    }
+
+    fn open(path: PathBuf, rolling: bool) -> SitemapResult<Self> {
+        let first = if rolling {
+            PathBuf::from(shard_path(&path.to_string_lossy(), 1))
+        } else {
+            path.clone()
+        };
+        let mut writer = start_shard(&first)?;
+        let header_len = writer.event_writer().inner_mut().count;
+        Ok(IncrementalSitemapWriter {
+            path,
+            rolling,
+            writer,
+            shard_len: 0,
+            len: 0,
+            paths: vec![first],
+            header_len,
+            size_limit: MAX_SITEMAP_SIZE,
+        })
+    }
+
+    /// Lowers the size at which a shard is considered full.
+    ///
+    /// # Arguments
+    /// * `bytes` - The maximum size of a shard in bytes. Values above the
+    ///   10MB protocol limit are clamped to it.
+    pub fn with_shard_size_limit(mut self, bytes: usize) -> Self {
+        self.size_limit = bytes.min(MAX_SITEMAP_SIZE);
+        self
+    }
+
+    /// Appends a `<url>` element for `entry` to the current file.
+    ///
+    /// # Arguments
+    /// * `entry` - The entry to write.
+    ///
+    /// # Returns
+    /// `Ok(())` on success, or an error if writing fails, the entry alone
+    /// exceeds 10MB or, for a non-rolling writer, the entry would exceed a
+    /// sitemap limit.
+    pub fn add_entry(
+        &mut self,
+        entry: &SiteMapData,
+    ) -> SitemapResult<()> {
+        let mut entry_size = entry_size_bound(entry);
+        let mut size = self.writer_size() + entry_size;
+        if size + CLOSING_TAG_LEN > self.size_limit {
+            // The bound is pessimistic, so measure before giving up.
+            entry_size = serialized_size(entry)?;
+            size = self.writer_size() + entry_size;
+        }
+        let alone = self.header_len + entry_size + CLOSING_TAG_LEN;
+        if alone > MAX_SITEMAP_SIZE {
+            return Err(SitemapError::UrlTooLarge {
+                url: entry.loc.to_string(),
+                size: alone,
+            });
+        }
+
+        let full = self.shard_len == MAX_URLS
+            || size + CLOSING_TAG_LEN > self.size_limit;
+        if full && self.rolling && alone > self.size_limit {
+            warn!(
+                "Entry {} needs {} bytes, over the shard limit of {}; writing it to its own shard",
+                entry.loc, alone, self.size_limit
+            );
+        }
+        // An oversized entry can't fit any better in a new shard, so an
+        // empty one is kept rather than left behind.
+        if full && !(self.rolling && self.shard_len == 0) {
+            if !self.rolling {
+                return Err(if self.shard_len == MAX_URLS {
+                    SitemapError::MaxUrlLimitExceeded(
+                        self.shard_len + 1,
+                    )
+                } else {
+                    SitemapError::SitemapTooLarge {
+                        size,
+                        limit: self.size_limit,
+                    }
+                });
+            }
+            self.roll()?;
+        }
+
+        self.writer.write_entry(entry)?;
+        self.shard_len += 1;
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Returns the total number of entries written across all shards.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if no entries have been written yet.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Closes the current file and returns the paths of all files written.
+    pub fn finish(self) -> SitemapResult<Vec<PathBuf>> {
+        finish_shard(self.writer)?;
+        Ok(self.paths)
+    }
+
+    /// Closes the current shard and starts the next one.
+    fn roll(&mut self) -> SitemapResult<()> {
+        let next = PathBuf::from(shard_path(
+            &self.path.to_string_lossy(),
+            self.paths.len() + 1,
+        ));
+        let previous =
+            std::mem::replace(&mut self.writer, start_shard(&next)?);
+        finish_shard(previous)?;
+        self.paths.push(next);
+        self.shard_len = 0;
+        Ok(())
+    }
+
+    /// Returns the number of bytes written to the current shard so far.
+    fn writer_size(&mut self) -> usize {
+        self.writer.event_writer().inner_mut().count
+    }
+}
+
+/// Creates `path` and writes the sitemap header to it.
+fn start_shard(
+    path: &Path,
+) -> SitemapResult<SitemapWriter<ByteCounter<BufWriter<File>>>> {
+    let file = BufWriter::new(File::create(path)?);
+    let mut writer = SitemapWriter::new(ByteCounter::new(file));
+    writer.start()?;
+    Ok(writer)
+}
+
+/// Writes the closing tag of a shard and flushes it to disk.
+fn finish_shard(
+    writer: SitemapWriter<ByteCounter<BufWriter<File>>>,
+) -> SitemapResult<()> {
+    let mut counter = writer.finish()?;
+    counter.flush()?;
+    Ok(())
+}
+
+/// Returns the exact size of the `<url>` element written for `entry`.
+fn serialized_size(entry: &SiteMapData) -> SitemapResult<usize> {
+    let mut writer = SitemapWriter::new(ByteCounter::new(io::sink()));
+    writer.start()?;
+    let start = writer.event_writer().inner_mut().count;
+    writer.write_entry(entry)?;
+    Ok(writer.event_writer().inner_mut().count - start)
+}
+
+/// Returns an upper bound on the serialized size of `entry`.
+///
+/// Escaping can expand a character to at most five bytes (`&amp;`), and
+/// the surrounding tags, change frequency and priority fit in the fixed
+/// overhead. Each alternate, image and video also gets a fixed allowance
+/// for its tags and namespace declaration.
+fn entry_size_bound(entry: &SiteMapData) -> usize {
+    let extra: usize = entry
+        .extra
+        .iter()
+        .map(|element| {
+            2 * element.name.len()
+                + element.value.len() * 5
+                + element
+                    .namespace
+                    .as_ref()
+                    .map_or(0, |namespace| namespace.len() * 5 + 16)
+                + 5
+        })
+        .sum();
+    #[cfg(feature = "image-sitemap")]
+    let images: usize = entry
+        .images
+        .iter()
+        .map(|image| {
+            let text = [
+                image.caption.as_deref(),
+                image.title.as_deref(),
+                image.geo_location.as_deref(),
+                image.license.as_ref().map(Url::as_str),
+            ]
+            .iter()
+            .flatten()
+            .map(|value| value.len())
+            .sum::<usize>();
+            (image.loc.as_str().len() + text) * 5 + 256
+        })
+        .sum();
+    #[cfg(not(feature = "image-sitemap"))]
+    let images = 0;
+    let videos: usize = entry
+        .videos
+        .iter()
+        .map(|video| {
+            let text = [
+                video.content_loc.as_ref().map(Url::as_str),
+                video.player_loc.as_ref().map(Url::as_str),
+                video.publication_date.as_deref(),
+            ]
+            .iter()
+            .flatten()
+            .map(|value| value.len())
+            .sum::<usize>();
+            (video.thumbnail_loc.as_str().len()
+                + video.title.len()
+                + video.description.len()
+                + text)
+                * 5
+                + 512
+        })
+        .sum();
+    let alternates: usize = entry
+        .alternates
+        .iter()
+        .map(|alternate| {
+            (alternate.hreflang.len() + alternate.href.as_str().len())
+                * 5
+                + 128
+        })
+        .sum();
+    (entry.loc.as_str().len() + entry.lastmod.len()) * 5
+        + extra
+        + alternates
+        + images
+        + videos
+        + 128
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sitemap::{ChangeFreq, Sitemap};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_incremental_writer_round_trip() -> SitemapResult<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("sitemap.xml");
+
+        let mut writer = IncrementalSitemapWriter::create(&path)?;
+        for i in 0..1_000 {
+            writer.add_entry(&SiteMapData {
+                loc: Url::parse(&format!(
+                    "https://example.com/page-{}",
+                    i
+                ))?,
+                lastmod: "2024-10-08".to_string(),
+                changefreq: ChangeFreq::Weekly,
+                priority: None,
+                extra: Vec::new(),
+                images: Vec::new(),
+                videos: Vec::new(),
+                alternates: Vec::new(),
+            })?;
+        }
+        assert_eq!(writer.len(), 1_000);
+        assert_eq!(writer.finish()?, vec![path.clone()]);
+
+        let sitemap =
+            Sitemap::from_xml(&std::fs::read_to_string(path)?)?;
+        assert_eq!(sitemap.len(), 1_000);
+        assert_eq!(
+            sitemap.iter().nth(999).map(|entry| entry.loc.as_str()),
+            Some("https://example.com/page-999")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_incremental_writer_rolls_at_url_limit() -> SitemapResult<()>
+    {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("sitemap.xml");
+        let entry = SiteMapData {
+            loc: Url::parse("https://example.com/")?,
+            lastmod: "2024-10-08".to_string(),
+            changefreq: ChangeFreq::Weekly,
+            priority: None,
+            extra: Vec::new(),
+            images: Vec::new(),
+            videos: Vec::new(),
+            alternates: Vec::new(),
+        };
+
+        let mut writer =
+            IncrementalSitemapWriter::create_rolling(&path)?;
+        for _ in 0..=MAX_URLS {
+            writer.add_entry(&entry)?;
+        }
+        let paths = writer.finish()?;
+
+        assert_eq!(
+            paths,
+            vec![
+                dir.path().join("sitemap-1.xml"),
+                dir.path().join("sitemap-2.xml")
+            ]
+        );
+        let last =
+            Sitemap::from_xml(&std::fs::read_to_string(&paths[1])?)?;
+        assert_eq!(last.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_incremental_writer_isolates_oversized_entry(
+    ) -> SitemapResult<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("sitemap.xml");
+        let entry = |path: &str| {
+            SiteMapData::new(
+                Url::parse("https://example.com")
+                    .unwrap()
+                    .join(path)
+                    .unwrap(),
+                "2024-10-08",
+                ChangeFreq::Weekly,
+            )
+        };
+        let giant = entry("/giant")
+            .with_extra("note", "x".repeat(64 * 1024))?;
+
+        let mut writer =
+            IncrementalSitemapWriter::create_rolling(&path)?
+                .with_shard_size_limit(16 * 1024);
+        for entry in [entry("/a"), giant, entry("/b")] {
+            writer.add_entry(&entry)?;
+        }
+        let paths = writer.finish()?;
+
+        let shards = paths
+            .iter()
+            .map(|path| {
+                Sitemap::from_xml(&std::fs::read_to_string(path)?)
+            })
+            .collect::<SitemapResult<Vec<_>>>()?;
+        let locs: Vec<Vec<&str>> = shards
+            .iter()
+            .map(|shard| shard.iter_urls().map(Url::path).collect())
+            .collect();
+        assert_eq!(locs, [["/a"], ["/giant"], ["/b"]]);
+
+        let too_large = entry("/huge")
+            .with_extra("note", "x".repeat(MAX_SITEMAP_SIZE))?;
+        let mut writer =
+            IncrementalSitemapWriter::create_rolling(&path)?;
+        assert!(matches!(
+            writer.add_entry(&too_large),
+            Err(SitemapError::UrlTooLarge { .. })
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_incremental_writer_reports_shard_size_limit(
+    ) -> SitemapResult<()> {
+        let dir = TempDir::new()?;
+        let mut writer =
+            IncrementalSitemapWriter::create(dir.path().join("s.xml"))?
+                .with_shard_size_limit(16 * 1024);
+        let entry = SiteMapData::new(
+            Url::parse("https://example.com/")?,
+            "2024-10-08",
+            ChangeFreq::Weekly,
+        )
+        .with_extra("note", "x".repeat(32 * 1024))?;
+
+        assert!(matches!(
+            writer.add_entry(&entry),
+            Err(SitemapError::SitemapTooLarge { limit, .. })
+                if limit == 16 * 1024
+        ));
+        Ok(())
+    }
+}
@@ -0,0 +1,290 @@
+// src/sitemap/index.rs
+
+use super::{
+    estimated_size, into_checked_xml, write_element, MAX_URLS,
+    SITEMAP_NS,
+};
+use crate::error::{SitemapError, SitemapResult};
+use url::Url;
+use xml::reader::{EventReader, XmlEvent as ReaderEvent};
+use xml::writer::{EventWriter, XmlEvent};
+
+/// A child sitemap referenced by a sitemap index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SitemapIndexEntry {
+    /// The location of the child sitemap.
+    pub loc: Url,
+    /// The last modification date of the child sitemap, empty if unknown.
+    pub lastmod: String,
+}
+
+/// Represents a sitemap index, listing the locations of child sitemaps.
+///
+/// Sites with more than 50,000 URLs split them over several sitemaps and
+/// publish an index referencing each of them. An index is subject to the
+/// same limits as a sitemap: at most 50,000 children and 10MB of XML.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SitemapIndex {
+    entries: Vec<SitemapIndexEntry>,
+}
+
+impl SitemapIndex {
+    /// Creates a new empty `SitemapIndex`.
+    pub fn new() -> Self {
+        SitemapIndex {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Returns the child sitemaps, in document order.
+    pub fn entries(&self) -> &[SitemapIndexEntry] {
+        &self.entries
+    }
+
+    /// Returns the number of child sitemaps.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Checks if the index is empty.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Adds a child sitemap to the index.
+    ///
+    /// # Arguments
+    /// * `loc` - The location of the child sitemap.
+    /// * `lastmod` - The last modification date of the child sitemap, or an
+    ///   empty string to omit `<lastmod>`.
+    ///
+    /// # Returns
+    /// `Ok(())` if the sitemap was added, or an error if the index already
+    /// holds 50,000 children.
+    pub fn add_sitemap(
+        &mut self,
+        loc: Url,
+        lastmod: String,
+    ) -> SitemapResult<()> {
+        if self.entries.len() >= MAX_URLS {
+            return Err(SitemapError::MaxUrlLimitExceeded(
+                self.entries.len(),
+            ));
+        }
+        self.entries.push(SitemapIndexEntry { loc, lastmod });
+        Ok(())
+    }
+
+    /// Generates the XML representation of the index.
+    ///
+    /// # Returns
+    /// A string containing the `<sitemapindex>` document, or an error if
+    /// generation fails or the document exceeds 10MB.
+    pub fn to_xml(&self) -> SitemapResult<String> {
+        let mut output = Vec::with_capacity(estimated_size(self.len()));
+        let mut writer = EventWriter::new(&mut output);
+
+        writer.write(XmlEvent::StartDocument {
+            version: xml::common::XmlVersion::Version10,
+            encoding: Some("UTF-8"),
+            standalone: None,
+        })?;
+        writer.write(
+            XmlEvent::start_element("sitemapindex")
+                .default_ns(SITEMAP_NS),
+        )?;
+        for entry in &self.entries {
+            writer.write(XmlEvent::start_element("sitemap"))?;
+            write_element(&mut writer, "loc", entry.loc.as_str())?;
+            if !entry.lastmod.is_empty() {
+                write_element(&mut writer, "lastmod", &entry.lastmod)?;
+            }
+            writer.write(XmlEvent::end_element())?;
+        }
+        writer.write(XmlEvent::end_element())?;
+
+        into_checked_xml(output)
+    }
+
+    /// Parses a sitemap index from its XML representation.
+    ///
+    /// Only the `<loc>` and `<lastmod>` children of each `<sitemap>` element
+    /// are read; other elements are skipped.
+    ///
+    /// # Arguments
+    /// * `xml` - The XML document to parse.
+    ///
+    /// # Returns
+    /// The parsed `SitemapIndex`, or an error if the document is malformed, the
+    /// root element isn't `<sitemapindex>`, a `<sitemap>` has no valid `<loc>`,
+    /// or there are more than 50,000 `<sitemap>` entries.
+    pub fn from_xml(xml: &str) -> SitemapResult<SitemapIndex> {
+        let mut index = SitemapIndex::new();
+        let mut depth = 0usize;
+        let mut child: Option<(Option<Url>, String)> = None;
+        let mut field: Option<String> = None;
+        let mut text = String::new();
+
+        for event in EventReader::from_str(xml) {
+            match event? {
+                ReaderEvent::StartElement { name, .. } => {
+                    depth += 1;
+                    let in_sitemap_ns = name
+                        .namespace
+                        .as_deref()
+                        .map_or(true, |ns| ns == SITEMAP_NS);
+                    match depth {
+                        1 if name.local_name != "sitemapindex" => {
+                            return Err(SitemapError::CustomError(
+                                format!(
+                                    "Expected <sitemapindex> root element, found <{}>",
+                                    name.local_name
+                                ),
+                            ));
+                        }
+                        2 if in_sitemap_ns
+                            && name.local_name == "sitemap" =>
+                        {
+                            child = Some((None, String::new()));
+                        }
+                        3 if in_sitemap_ns && child.is_some() => {
+                            field = Some(name.local_name);
+                            text.clear();
+                        }
+                        _ => {}
+                    }
+                }
+                ReaderEvent::Characters(data)
+                | ReaderEvent::CData(data)
+                    if depth == 3 && field.is_some() =>
+                {
+                    text.push_str(&data);
+                }
+                ReaderEvent::EndElement { .. } => {
+                    match depth {
+                        2 => {
+                            if let Some((loc, lastmod)) = child.take() {
+                                let loc = loc.ok_or_else(|| {
+                                    SitemapError::CustomError(
+                                        "Missing <loc> in <sitemap> entry"
+                                            .to_string(),
+                                    )
+                                })?;
+                                index.add_sitemap(loc, lastmod)?;
+                            }
+                        }
+                        3 => {
+                            if let (Some(name), Some((loc, lastmod))) =
+                                (field.take(), child.as_mut())
+                            {
+                                match name.as_str() {
+                                    "loc" => {
+                                        *loc = Some(Url::parse(
+                                            text.trim(),
+                                        )?)
+                                    }
+                                    "lastmod" => {
+                                        *lastmod = text.clone()
+                                    }
+                                    _ => {}
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                    depth -= 1;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_from_xml() -> SitemapResult<()> {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+  <sitemap>
+    <loc>https://example.com/sitemap-1.xml</loc>
+    <lastmod>2024-10-08</lastmod>
+  </sitemap>
+  <sitemap>
+    <loc>https://example.com/sitemap-2.xml</loc>
+  </sitemap>
+</sitemapindex>"#;
+
+        let index = SitemapIndex::from_xml(xml)?;
+        assert_eq!(index.len(), 2);
+        assert_eq!(
+            index.entries()[0].loc.as_str(),
+            "https://example.com/sitemap-1.xml"
+        );
+        assert_eq!(index.entries()[0].lastmod, "2024-10-08");
+        assert_eq!(index.entries()[1].lastmod, "");
+
+        assert!(SitemapIndex::from_xml("<urlset></urlset>").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_index_to_xml_round_trip() -> SitemapResult<()> {
+        let mut index = SitemapIndex::new();
+        index.add_sitemap(
+            Url::parse("https://example.com/sitemap-1.xml")?,
+            "2024-10-08".to_string(),
+        )?;
+        index.add_sitemap(
+            Url::parse("https://example.com/sitemap-2.xml")?,
+            String::new(),
+        )?;
+
+        let xml = index.to_xml()?;
+        assert!(xml.contains(
+            "<sitemapindex xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">"
+        ));
+        assert!(xml.contains(
+            "<sitemap><loc>https://example.com/sitemap-1.xml</loc><lastmod>2024-10-08</lastmod></sitemap>"
+        ));
+        assert!(xml.contains(
+            "<sitemap><loc>https://example.com/sitemap-2.xml</loc></sitemap>"
+        ));
+        assert_eq!(SitemapIndex::from_xml(&xml)?, index);
+        Ok(())
+    }
+
+    #[test]
+    fn test_index_child_limit() -> SitemapResult<()> {
+        let loc = Url::parse("https://example.com/sitemap.xml")?;
+        let mut index = SitemapIndex::new();
+        for _ in 0..MAX_URLS {
+            index.add_sitemap(loc.clone(), String::new())?;
+        }
+        assert!(matches!(
+            index.add_sitemap(loc.clone(), String::new()),
+            Err(SitemapError::MaxUrlLimitExceeded(MAX_URLS))
+        ));
+
+        let child = format!("<sitemap><loc>{}</loc></sitemap>", loc);
+        let xml = |children: usize| {
+            format!(
+                "<sitemapindex>{}</sitemapindex>",
+                child.repeat(children)
+            )
+        };
+        assert_eq!(
+            SitemapIndex::from_xml(&xml(MAX_URLS))?.len(),
+            MAX_URLS
+        );
+        assert!(matches!(
+            SitemapIndex::from_xml(&xml(MAX_URLS + 1)),
+            Err(SitemapError::MaxUrlLimitExceeded(MAX_URLS))
+        ));
+        Ok(())
+    }
+}
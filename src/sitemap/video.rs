@@ -0,0 +1,200 @@
+// src/sitemap/video.rs
+
+use super::write_element;
+use crate::error::{SitemapError, SitemapResult};
+use std::io::Write;
+use url::Url;
+use xml::writer::{EventWriter, XmlEvent};
+
+/// The XML namespace of Google's video sitemap extension.
+pub(crate) const VIDEO_NS: &str =
+    "http://www.google.com/schemas/sitemap-video/1.1";
+
+/// A video on a page, written as a `<video:video>` element of the page's
+/// `<url>` entry.
+///
+/// Google requires a thumbnail, title and description, and at least one
+/// of `content_loc` and `player_loc`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VideoEntry {
+    /// The location of the video's thumbnail image.
+    pub thumbnail_loc: Url,
+    /// The title of the video.
+    pub title: String,
+    /// A description of the video.
+    pub description: String,
+    /// The location of the video media file.
+    pub content_loc: Option<Url>,
+    /// The location of a player for the video.
+    pub player_loc: Option<Url>,
+    /// The duration of the video in seconds.
+    pub duration: Option<u32>,
+    /// The rating of the video, from 0.0 to 5.0.
+    pub rating: Option<f32>,
+    /// The number of times the video has been viewed.
+    pub view_count: Option<u64>,
+    /// The date the video was first published, in W3C Datetime format.
+    pub publication_date: Option<String>,
+}
+
+impl VideoEntry {
+    /// Creates a video entry with only the required descriptive fields.
+    ///
+    /// A `content_loc` or `player_loc` must still be set before the entry
+    /// is added to a page.
+    ///
+    /// # Arguments
+    /// * `thumbnail_loc` - The location of the video's thumbnail image.
+    /// * `title` - The title of the video.
+    /// * `description` - A description of the video.
+    pub fn new(
+        thumbnail_loc: Url,
+        title: impl Into<String>,
+        description: impl Into<String>,
+    ) -> Self {
+        VideoEntry {
+            thumbnail_loc,
+            title: title.into(),
+            description: description.into(),
+            content_loc: None,
+            player_loc: None,
+            duration: None,
+            rating: None,
+            view_count: None,
+            publication_date: None,
+        }
+    }
+}
+
+/// Checks that `video`, on the page at `url`, has a location to play it
+/// from.
+pub(crate) fn check_video(
+    url: &str,
+    video: &VideoEntry,
+) -> SitemapResult<()> {
+    if video.content_loc.is_none() && video.player_loc.is_none() {
+        return Err(SitemapError::MissingVideoLocation {
+            url: url.to_string(),
+            title: video.title.clone(),
+        });
+    }
+    Ok(())
+}
+
+/// Writes `video`, on the page at `url`, as a `<video:video>` element.
+///
+/// When `declare_ns` is set, the element declares the `video` prefix
+/// itself, for documents whose `<urlset>` was written without it.
+pub(crate) fn write_video<W: Write>(
+    writer: &mut EventWriter<W>,
+    url: &str,
+    video: &VideoEntry,
+    declare_ns: bool,
+) -> SitemapResult<()> {
+    check_video(url, video)?;
+    let start = XmlEvent::start_element("video:video");
+    if declare_ns {
+        writer.write(start.ns("video", VIDEO_NS))?;
+    } else {
+        writer.write(start)?;
+    }
+    write_element(
+        writer,
+        "video:thumbnail_loc",
+        video.thumbnail_loc.as_str(),
+    )?;
+    write_element(writer, "video:title", &video.title)?;
+    write_element(writer, "video:description", &video.description)?;
+    let optional = [
+        (
+            "video:content_loc",
+            video.content_loc.as_ref().map(|loc| loc.to_string()),
+        ),
+        (
+            "video:player_loc",
+            video.player_loc.as_ref().map(|loc| loc.to_string()),
+        ),
+        ("video:duration", video.duration.map(|d| d.to_string())),
+        ("video:rating", video.rating.map(|r| format!("{:.1}", r))),
+        ("video:view_count", video.view_count.map(|v| v.to_string())),
+        ("video:publication_date", video.publication_date.clone()),
+    ];
+    for (name, value) in optional {
+        if let Some(value) = value {
+            write_element(writer, name, &value)?;
+        }
+    }
+    writer.write(XmlEvent::end_element())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sitemap::{ChangeFreq, SiteMapData, Sitemap};
+
+    fn page() -> SiteMapData {
+        SiteMapData::new(
+            Url::parse("https://example.com/watch").unwrap(),
+            "2024-10-08",
+            ChangeFreq::Weekly,
+        )
+    }
+
+    fn video() -> VideoEntry {
+        VideoEntry::new(
+            Url::parse("https://example.com/thumb.jpg").unwrap(),
+            "Grilling steaks",
+            "Tips & tricks",
+        )
+    }
+
+    #[test]
+    fn test_videos_are_written() -> SitemapResult<()> {
+        let video = VideoEntry {
+            content_loc: Some(Url::parse("https://example.com/v.mp4")?),
+            duration: Some(600),
+            rating: Some(4.2),
+            ..video()
+        };
+        let mut sitemap = Sitemap::new();
+        sitemap.add_entry(page().with_video(video)?)?;
+
+        let xml = sitemap.to_xml()?;
+        assert!(xml.contains(&format!("xmlns:video=\"{}\"", VIDEO_NS)));
+        assert!(xml.contains(
+            "<video:video>\
+             <video:thumbnail_loc>https://example.com/thumb.jpg\
+             </video:thumbnail_loc>\
+             <video:title>Grilling steaks</video:title>\
+             <video:description>Tips &amp; tricks</video:description>\
+             <video:content_loc>https://example.com/v.mp4\
+             </video:content_loc>\
+             <video:duration>600</video:duration>\
+             <video:rating>4.2</video:rating>\
+             </video:video></url>"
+        ));
+        assert_eq!(Sitemap::from_xml(&xml)?.len(), 1);
+
+        let mut plain = Sitemap::new();
+        plain.add_entry(page())?;
+        assert!(!plain.to_xml()?.contains("xmlns:video"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_video_requires_location() -> SitemapResult<()> {
+        assert!(matches!(
+            page().with_video(video()),
+            Err(SitemapError::MissingVideoLocation { ref title, .. })
+                if title == "Grilling steaks"
+        ));
+
+        let mut entry = page();
+        entry.videos.push(video());
+        let mut sitemap = Sitemap::new();
+        sitemap.add_entry(entry)?;
+        assert!(sitemap.to_xml().is_err());
+        Ok(())
+    }
+}
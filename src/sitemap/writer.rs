@@ -0,0 +1,145 @@
+// src/sitemap/writer.rs
+
+use super::{
+    lastmod_text, write_element, write_url, write_urlset_start,
+    LastmodPrecision, SiteMapData,
+};
+use crate::error::SitemapResult;
+use std::fmt;
+use std::io::Write;
+use xml::writer::{EventWriter, XmlEvent};
+
+/// A low-level writer for building sitemap documents element by element.
+///
+/// `SitemapWriter` writes the same `<urlset>` document as
+/// [`Sitemap::to_xml`](super::Sitemap::to_xml) with the default settings,
+/// but lets advanced users interleave their own elements between entries,
+/// either with [`SitemapWriter::write_element`] or through the underlying
+/// [`EventWriter`].
+///
+/// No size or URL count limits are enforced.
+pub struct SitemapWriter<W: Write> {
+    writer: EventWriter<W>,
+}
+
+impl<W: Write> fmt::Debug for SitemapWriter<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SitemapWriter").finish_non_exhaustive()
+    }
+}
+
+impl<W: Write> SitemapWriter<W> {
+    /// Creates a writer emitting the document to `inner`.
+    pub fn new(inner: W) -> Self {
+        SitemapWriter {
+            writer: EventWriter::new(inner),
+        }
+    }
+
+    /// Writes the XML declaration and the opening `<urlset>` element.
+    pub fn start(&mut self) -> SitemapResult<()> {
+        write_urlset_start(&mut self.writer, "UTF-8", &[])
+    }
+
+    /// Writes a `<url>` element for `entry`.
+    ///
+    /// Datetime `lastmod` values are truncated to their date, as with the
+    /// default sitemap settings. Since the `<urlset>` is written before the
+    /// entries are known, each `<xhtml:link>`, `<image:image>` and
+    /// `<video:video>` element declares its namespace itself.
+    pub fn write_entry(
+        &mut self,
+        entry: &SiteMapData,
+    ) -> SitemapResult<()> {
+        write_url(
+            &mut self.writer,
+            entry.loc.as_str(),
+            &lastmod_text(&entry.lastmod, LastmodPrecision::DateOnly),
+            Some(entry.changefreq.as_str()),
+            entry.priority,
+            entry.extensions(),
+            true,
+            false,
+        )
+    }
+
+    /// Writes a simple `<name>text</name>` element.
+    pub fn write_element(
+        &mut self,
+        name: &str,
+        text: &str,
+    ) -> SitemapResult<()> {
+        write_element(&mut self.writer, name, text)
+    }
+
+    /// Returns the underlying event writer, for writing arbitrary events.
+    pub fn event_writer(&mut self) -> &mut EventWriter<W> {
+        &mut self.writer
+    }
+
+    /// Closes the `<urlset>` element and returns the inner writer.
+    pub fn finish(mut self) -> SitemapResult<W> {
+        self.writer.write(XmlEvent::end_element())?;
+        Ok(self.writer.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sitemap::{ChangeFreq, Sitemap};
+    use url::Url;
+
+    #[test]
+    fn test_writer_matches_to_xml() -> SitemapResult<()> {
+        let entries = [
+            SiteMapData {
+                loc: Url::parse("https://example.com/")?,
+                lastmod: "2024-10-08T10:00:00Z".to_string(),
+                changefreq: ChangeFreq::Daily,
+                priority: None,
+                extra: Vec::new(),
+                images: Vec::new(),
+                videos: Vec::new(),
+                alternates: Vec::new(),
+            },
+            SiteMapData {
+                loc: Url::parse("https://example.com/?a=1&b=2")?,
+                lastmod: "2024-10-09".to_string(),
+                changefreq: ChangeFreq::Monthly,
+                priority: None,
+                extra: Vec::new(),
+                images: Vec::new(),
+                videos: Vec::new(),
+                alternates: Vec::new(),
+            },
+        ];
+        let mut sitemap = Sitemap::new();
+        let mut writer = SitemapWriter::new(Vec::new());
+        writer.start()?;
+        for entry in entries {
+            writer.write_entry(&entry)?;
+            sitemap.add_entry(entry)?;
+        }
+        let output = writer.finish()?;
+
+        assert_eq!(String::from_utf8(output)?, sitemap.to_xml()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_writer_custom_elements() -> SitemapResult<()> {
+        let mut writer = SitemapWriter::new(Vec::new());
+        writer.start()?;
+        writer
+            .event_writer()
+            .write(XmlEvent::comment(" generated nightly "))?;
+        writer.write_element("note", "custom")?;
+        let xml = String::from_utf8(writer.finish()?)?;
+
+        assert!(xml.contains("<!-- generated nightly -->"));
+        assert!(xml.contains("<note>custom</note>"));
+        assert!(xml.ends_with("</urlset>"));
+        Ok(())
+    }
+}
@@ -1,5 +1,6 @@
 use crate::{
-    ChangeFreq, SiteMapData, Sitemap, SitemapError, SitemapResult,
+    ChangeFreq, ChangeFreqAliases, SiteMapData, Sitemap, SitemapError,
+    SitemapResult,
 };
 use clap::{Arg, ArgAction, Command};
 use dtt::{datetime::DateTime, dtt_now};
@@ -7,7 +8,19 @@
 use log::{info, warn};
 use std::io::BufRead;
 use std::io::Write;
-use std::{collections::HashSet, fs::File, io};
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread;
+use std::time::Duration;
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    fs::File,
+    io,
+};
 use url::Url;
 
 /// Maximum number of URLs allowed in a single sitemap.
@@ -16,6 +29,24 @@
 /// Default change frequency for URLs.
 pub const DEFAULT_CHANGE_FREQ: &str = "weekly";
 
+/// Maximum indentation width accepted by `--indent-size`.
+pub const MAX_INDENT_SIZE: usize = 8;
+
+/// Returns the crate version along with the versions of key dependencies.
+///
+/// Dependency versions are resolved from `Cargo.lock` at build time, so bug
+/// reports can include the exact `url`, `xml-rs` and `dtt` versions compiled
+/// in.
+pub fn version_full() -> String {
+    format!(
+        "sitemap-gen {}\nurl {}\nxml-rs {}\ndtt {}",
+        env!("CARGO_PKG_VERSION"),
+        env!("SITEMAP_GEN_URL_VERSION"),
+        env!("SITEMAP_GEN_XML_VERSION"),
+        env!("SITEMAP_GEN_DTT_VERSION"),
+    )
+}
+
 /// Creates the command-line interface for the application.
 ///
 /// This function defines all the possible arguments and options
@@ -25,6 +56,12 @@ pub fn create_cli() -> Command {
         .version(env!("CARGO_PKG_VERSION"))
         .author("Your Name <your.email@example.com>")
         .about("Generates XML sitemaps")
+        .arg(
+            Arg::new("version-full")
+                .long("version-full")
+                .help("Prints version information, including key dependency versions")
+                .action(ArgAction::SetTrue),
+        )
         .subcommand(
             Command::new("generate")
                 .about("Generates a sitemap")
@@ -50,9 +87,17 @@ pub fn create_cli() -> Command {
                         .short('i')
                         .long("input")
                         .value_name("FILE")
-                        .help("Read URLs from a file")
+                        .help("Read URLs from a file, from every file matching a pattern such as 'urls/*.txt', or from stdin if FILE is '-'")
+                        .action(ArgAction::Append)
                         .conflicts_with("url"),
                 )
+                .arg(
+                    Arg::new("csv")
+                        .long("csv")
+                        .value_name("FILE")
+                        .help("Read URLs with their lastmod, changefreq and priority from a CSV file with a header row")
+                        .conflicts_with_all(["url", "input"]),
+                )
                 .arg(
                     Arg::new("changefreq")
                         .short('c')
@@ -61,6 +106,129 @@ pub fn create_cli() -> Command {
                         .help("Sets the change frequency for all URLs")
                         .default_value(DEFAULT_CHANGE_FREQ),
                 )
+                .arg(
+                    Arg::new("changefreq-alias")
+                        .long("changefreq-alias")
+                        .value_name("ALIAS=FREQ")
+                        .help("Accepts ALIAS as an alternative name for FREQ")
+                        .action(ArgAction::Append),
+                )
+                .arg(
+                    Arg::new("priority")
+                        .short('p')
+                        .long("priority")
+                        .value_name("PRIORITY")
+                        .help("Sets the priority, from 0.0 to 1.0, for all URLs")
+                        .value_parser(clap::value_parser!(f32)),
+                )
+                .arg(
+                    Arg::new("limit")
+                        .long("limit")
+                        .value_name("N")
+                        .help("Includes at most N URLs from the input (N >= 1)")
+                        .value_parser(
+                            clap::builder::RangedU64ValueParser::<usize>::new()
+                                .range(1..),
+                        ),
+                )
+                .arg(
+                    Arg::new("fail-on-duplicates")
+                        .long("fail-on-duplicates")
+                        .help("Fails if duplicate URLs are found")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("no-normalize")
+                        .long("no-normalize")
+                        .help("Emits URLs verbatim, skipping normalization and deduplication")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("split-threshold")
+                        .long("split-threshold")
+                        .value_name("N")
+                        .help("Splits the output into sitemaps of at most N URLs each (N <= 50000)")
+                        .value_parser(
+                            clap::builder::RangedU64ValueParser::<usize>::new()
+                                .range(1..=MAX_URLS as u64),
+                        ),
+                )
+                .arg(
+                    Arg::new("output-template")
+                        .long("output-template")
+                        .value_name("PATTERN")
+                        .help("Names split sitemaps after PATTERN, where {n} is the shard number and {base} the output file stem")
+                        .requires("split-threshold"),
+                )
+                .arg(
+                    Arg::new("single-host")
+                        .long("single-host")
+                        .help("Fails if the URLs span more than one host")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("keep-tracking-params")
+                        .long("keep-tracking-params")
+                        .help("Keeps tracking and session query parameters such as utm_* and fbclid")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("fail-on-empty")
+                        .long("fail-on-empty")
+                        .help("Fails if no URLs remain after normalization")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("timeout")
+                        .long("timeout")
+                        .value_name("SECS")
+                        .help("Aborts generation if it takes longer than SECS seconds")
+                        .value_parser(clap::value_parser!(u64)),
+                )
+                .arg(
+                    Arg::new("format")
+                        .long("format")
+                        .value_name("FORMAT")
+                        .help("Sets the output format: xml, txt (one URL per line) or json (requires the json feature)")
+                        .value_parser(["xml", "txt", "json"])
+                        .default_value("xml"),
+                )
+                .arg(
+                    Arg::new("pretty")
+                        .long("pretty")
+                        .help("Pretty-prints the generated XML")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("indent-size")
+                        .long("indent-size")
+                        .value_name("N")
+                        .help("Indents pretty-printed XML by N spaces per level (N <= 8)")
+                        .requires("pretty")
+                        .default_value("2")
+                        .value_parser(
+                            clap::builder::RangedU64ValueParser::<usize>::new()
+                                .range(0..=MAX_INDENT_SIZE as u64),
+                        ),
+                )
+                .arg(
+                    Arg::new("also-stdout")
+                        .long("also-stdout")
+                        .help("Also prints the generated XML to stdout")
+                        .action(ArgAction::SetTrue),
+                )
+                .arg(
+                    Arg::new("robots")
+                        .long("robots")
+                        .value_name("FILE")
+                        .help("Writes a robots.txt pointing at the generated sitemaps, assumed to be served from the site root"),
+                )
+                .arg(
+                    Arg::new("report")
+                        .long("report")
+                        .value_name("FILE")
+                        .help("Writes a generation report to a file"),
+                )
                 .arg(
                     Arg::new("verbose")
                         .short('v')
@@ -88,38 +256,239 @@ pub fn create_cli() -> Command {
 /// - The number of URLs exceeds the maximum limit
 /// - Sitemap generation fails
 /// - Writing output files fails
+/// - Generation takes longer than the `--timeout`, if one is set
 pub fn generate_sitemap(
     matches: &clap::ArgMatches,
+) -> SitemapResult<()> {
+    let timeout = match matches.get_one::<u64>("timeout") {
+        Some(&timeout) => timeout,
+        None => {
+            return run_generation(
+                matches,
+                &GenerationOutputs::default(),
+            )
+        }
+    };
+
+    let (sender, receiver) = mpsc::channel();
+    let outputs = Arc::new(GenerationOutputs::default());
+    let thread_matches = matches.clone();
+    let thread_outputs = Arc::clone(&outputs);
+    let worker = thread::spawn(move || {
+        let _ = sender
+            .send(run_generation(&thread_matches, &thread_outputs));
+    });
+
+    match receiver.recv_timeout(Duration::from_secs(timeout)) {
+        Ok(result) => result,
+        Err(RecvTimeoutError::Timeout) => {
+            outputs.cancel();
+            Err(timed_out())
+        }
+        // The worker only hangs up without a result when it panics.
+        Err(RecvTimeoutError::Disconnected) => match worker.join() {
+            Err(payload) => panic::resume_unwind(payload),
+            Ok(()) => Err(SitemapError::CustomError(
+                "generation stopped without a result".to_string(),
+            )),
+        },
+    }
+}
+
+/// The error returned when generation exceeds the `--timeout`.
+fn timed_out() -> SitemapError {
+    SitemapError::CustomError("generation timed out".to_string())
+}
+
+/// The files written by a generation run, shared with
+/// [`generate_sitemap`] so that a run that times out can be cleaned up.
+#[derive(Debug, Default)]
+struct GenerationOutputs {
+    cancelled: AtomicBool,
+    created: Mutex<Vec<String>>,
+}
+
+impl GenerationOutputs {
+    /// Records `path` and runs `write` to create it, unless the run was
+    /// cancelled.
+    ///
+    /// The record is held while writing, so [`GenerationOutputs::cancel`]
+    /// waits for a write in progress and then removes its file.
+    fn create(
+        &self,
+        path: &str,
+        write: impl FnOnce() -> SitemapResult<()>,
+    ) -> SitemapResult<()> {
+        let mut created =
+            self.created.lock().unwrap_or_else(PoisonError::into_inner);
+        if self.cancelled.load(Ordering::SeqCst) {
+            return Err(timed_out());
+        }
+        created.push(path.to_string());
+        write()
+    }
+
+    /// Stops further writes and removes the files created so far.
+    ///
+    /// Files the run didn't write, such as shards left by an earlier run,
+    /// are kept.
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+        let mut created =
+            self.created.lock().unwrap_or_else(PoisonError::into_inner);
+        for path in created.drain(..) {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Renders `sitemap` in the `--format` of the `generate` command.
+fn render_sitemap(
+    sitemap: &Sitemap,
+    format: &str,
+) -> SitemapResult<String> {
+    match format {
+        "txt" => Ok(sitemap
+            .iter_urls()
+            .map(|url| format!("{}\n", url))
+            .collect()),
+        #[cfg(feature = "json")]
+        "json" => sitemap.to_json(),
+        #[cfg(not(feature = "json"))]
+        "json" => Err(SitemapError::CustomError(
+            "JSON output requires the `json` feature".to_string(),
+        )),
+        _ => sitemap.to_xml(),
+    }
+}
+
+/// Runs the generation pipeline for [`generate_sitemap`], writing the
+/// output files through `outputs`.
+fn run_generation(
+    matches: &clap::ArgMatches,
+    outputs: &GenerationOutputs,
 ) -> SitemapResult<()> {
     let output_file = matches.get_one::<String>("output").unwrap();
     let verbose = matches.get_flag("verbose");
+    let template = matches.get_one::<String>("output-template");
+    if let Some(template) = template {
+        check_output_template(template)?;
+    }
+
+    let default_change_freq = DEFAULT_CHANGE_FREQ.to_string();
+    let changefreq_str = matches
+        .get_one::<String>("changefreq")
+        .unwrap_or(&default_change_freq);
+    let mut aliases = ChangeFreqAliases::new();
+    if let Some(specs) = matches.get_many::<String>("changefreq-alias")
+    {
+        for spec in specs {
+            let (alias, freq) = parse_changefreq_alias(spec)?;
+            aliases.register(alias, freq);
+        }
+    }
+    let changefreq = aliases.parse(changefreq_str)?;
+    let priority = matches.get_one::<f32>("priority").copied();
+    if let Some(priority) =
+        priority.filter(|priority| !(0.0..=1.0).contains(priority))
+    {
+        return Err(SitemapError::InvalidPriority(priority));
+    }
 
-    let urls = if let Some(input_file) =
-        matches.get_one::<String>("input")
+    let no_normalize = matches.get_flag("no-normalize");
+    let normalize_options = if matches.get_flag("keep-tracking-params")
     {
-        read_urls_from_file(input_file)?
+        NormalizeOptions::keep_all_params()
+    } else {
+        NormalizeOptions::default()
+    };
+
+    // Rows read with `--csv`, keyed by the URL they normalize to.
+    let mut csv_entries = HashMap::new();
+    let urls = if let Some(csv_file) = matches.get_one::<String>("csv")
+    {
+        let file =
+            File::open(csv_file).map_err(SitemapError::IoError)?;
+        let entries =
+            parse_csv_entries(io::BufReader::new(file), changefreq)?;
+        let urls =
+            entries.iter().map(|entry| entry.loc.clone()).collect();
+        for entry in entries {
+            let key = if no_normalize {
+                Some(entry.loc.clone())
+            } else {
+                normalize_url_with(
+                    entry.loc.clone(),
+                    &normalize_options,
+                )
+            };
+            if let Some(key) = key {
+                let _ = csv_entries.entry(key).or_insert(entry);
+            }
+        }
+        urls
+    } else if let Some(inputs) = matches.get_many::<String>("input") {
+        let mut urls = Vec::new();
+        for input in inputs {
+            for path in expand_input_pattern(input)? {
+                urls.extend(read_urls_from_file(
+                    &path.to_string_lossy(),
+                )?);
+            }
+        }
+        urls
     } else if let Some(url_values) = matches.get_many::<String>("url") {
         url_values
             .map(|s| Url::parse(s).map_err(SitemapError::UrlError))
             .collect::<Result<Vec<Url>, SitemapError>>()?
     } else {
         return Err(SitemapError::CustomError(
-            "No URLs provided. Use either -u or -i option.".to_string(),
+            "No URLs provided. Use the -u, -i or --csv option."
+                .to_string(),
         ));
     };
 
-    let urls = normalize_urls(urls);
+    let (mut urls, normalize_report) = if no_normalize {
+        retain_valid_urls(urls)
+    } else {
+        normalize_urls_with_options(urls, &normalize_options)
+    };
 
-    if urls.len() > MAX_URLS {
-        return Err(SitemapError::MaxUrlLimitExceeded(urls.len()));
+    if matches.get_flag("fail-on-duplicates")
+        && !normalize_report.duplicates.is_empty()
+    {
+        let duplicates: Vec<&str> = normalize_report
+            .duplicates
+            .iter()
+            .map(Url::as_str)
+            .collect();
+        return Err(SitemapError::CustomError(format!(
+            "Found {} duplicate URL(s): {}",
+            duplicates.len(),
+            duplicates.join(", ")
+        )));
     }
 
-    let default_change_freq = DEFAULT_CHANGE_FREQ.to_string();
-    let changefreq_str = matches
-        .get_one::<String>("changefreq")
-        .unwrap_or(&default_change_freq);
-    let changefreq = changefreq_str.parse::<ChangeFreq>()?;
+    if let Some(&limit) = matches.get_one::<usize>("limit") {
+        urls.truncate(limit);
+    }
+
+    if urls.is_empty() {
+        let message = "No URLs to add to the sitemap. Check that the input contains valid http(s) URLs.";
+        if matches.get_flag("fail-on-empty") {
+            return Err(SitemapError::CustomError(message.to_string()));
+        }
+        warn!("{}", message);
+    }
+
+    let split_threshold =
+        matches.get_one::<usize>("split-threshold").copied();
+    if split_threshold.is_none() && urls.len() > MAX_URLS {
+        return Err(SitemapError::MaxUrlLimitExceeded(urls.len()));
+    }
 
+    let shard_size = split_threshold.unwrap_or(MAX_URLS);
+    let mut shards = Vec::new();
     let mut sitemap = Sitemap::new();
 
     let progress_bar = if verbose {
@@ -148,153 +517,1288 @@ pub fn generate_sitemap(
             );
         }
 
-        let entry = SiteMapData {
-            loc: url.clone(),
-            lastmod: format_date(dtt_now!()),
-            changefreq,
+        if sitemap.len() == shard_size {
+            shards.push(std::mem::take(&mut sitemap));
+        }
+        let entry = match csv_entries.get(url) {
+            Some(entry) => SiteMapData {
+                loc: url.clone(),
+                priority: entry.priority.or(priority),
+                ..entry.clone()
+            },
+            None => SiteMapData {
+                loc: url.clone(),
+                lastmod: format_date(dtt_now!()),
+                changefreq,
+                priority,
+                extra: Vec::new(),
+                images: Vec::new(),
+                videos: Vec::new(),
+                alternates: Vec::new(),
+            },
         };
         sitemap.add_entry(entry)?;
     }
+    shards.push(sitemap);
+
+    if matches.get_flag("pretty") {
+        let indent_size = matches
+            .get_one::<usize>("indent-size")
+            .copied()
+            .unwrap_or(2);
+        shards = shards
+            .into_iter()
+            .map(|shard| shard.with_pretty_print(indent_size))
+            .collect();
+    }
 
     if let Some(pb) = progress_bar {
         pb.finish_with_message("Sitemap generation complete");
     }
 
+    if matches.get_flag("single-host") {
+        assert_single_host(&shards)?;
+    }
+
+    let (with_slash, without_slash) = shards
+        .iter()
+        .map(Sitemap::trailing_slash_report)
+        .fold((0, 0), |(a, b), (c, d)| (a + c, b + d));
+    if with_slash > 0 && without_slash > 0 {
+        warn!(
+            "Mixed trailing-slash conventions: {} URL(s) with a trailing slash, {} without",
+            with_slash, without_slash
+        );
+    }
+
     if verbose {
         info!("Writing sitemap to file...");
     }
 
-    let xml = sitemap.to_xml()?;
-    write_output(&xml, output_file)?;
+    let also_stdout = matches.get_flag("also-stdout");
+    let format = matches
+        .get_one::<String>("format")
+        .map_or("xml", String::as_str);
+    let mut byte_size = 0;
+    let mut written = Vec::with_capacity(shards.len());
+    for (index, shard) in shards.iter().enumerate() {
+        let xml = render_sitemap(shard, format)?;
+        byte_size += xml.len();
+        let path = if shards.len() == 1 {
+            output_file.to_string()
+        } else if let Some(template) = template {
+            template_shard_path(template, output_file, index + 1)
+        } else {
+            shard_path(output_file, index + 1)
+        };
+        outputs.create(&path, || {
+            if also_stdout {
+                let mut file = File::create(&path)
+                    .map_err(SitemapError::IoError)?;
+                let stdout = io::stdout();
+                let mut stdout = stdout.lock();
+                write_outputs(&xml, &mut [&mut file, &mut stdout])
+            } else {
+                write_output(&xml, &path)
+            }
+        })?;
+        written.push(path);
+    }
+
+    if let Some(robots_file) = matches.get_one::<String>("robots") {
+        // The sitemaps are assumed to be served from the site root.
+        let sitemap_urls = match urls.first() {
+            Some(site) => written
+                .iter()
+                .filter_map(|path| Path::new(path).file_name())
+                .filter_map(|name| {
+                    site.join(&format!("/{}", name.to_string_lossy()))
+                        .ok()
+                })
+                .collect(),
+            None => Vec::new(),
+        };
+        outputs.create(robots_file, || {
+            write_output(
+                &generate_robots_txt(&sitemap_urls),
+                robots_file,
+            )
+        })?;
+        if verbose {
+            info!("robots.txt written to {}", robots_file);
+        }
+    }
+
+    if let Some(report_file) = matches.get_one::<String>("report") {
+        let report = GenerationReport {
+            output: output_file.to_string(),
+            url_count: urls.len(),
+            byte_size,
+            shard_count: shards.len(),
+            skipped: normalize_report.skipped,
+            duplicates: normalize_report.duplicates,
+            generated_at: dtt_now!()
+                .format_rfc3339()
+                .unwrap_or_else(|_| format_date(dtt_now!())),
+        };
+        outputs.create(report_file, || {
+            write_output(&report.to_string(), report_file)
+        })?;
+        if verbose {
+            info!("Generation report written to {}", report_file);
+        }
+    }
 
     info!("Sitemap generated successfully: {}", output_file);
     Ok(())
 }
 
-/// Reads URLs from a file, one URL per line.
+/// Returns the path of the `index`-th shard of a split sitemap.
+///
+/// Shards are written next to the requested output, with the shard number
+/// appended to the file stem: `sitemap.xml` becomes `sitemap-1.xml`,
+/// `sitemap-2.xml`, and so on.
 ///
 /// # Arguments
 ///
-/// * `filename` - The name of the file to read URLs from
+/// * `output` - The requested output path
+/// * `index` - The 1-based shard number
+pub fn shard_path(output: &str, index: usize) -> String {
+    let path = Path::new(output);
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    let name = match path.extension() {
+        Some(ext) => {
+            format!("{}-{}.{}", stem, index, ext.to_string_lossy())
+        }
+        None => format!("{}-{}", stem, index),
+    };
+    path.with_file_name(name).to_string_lossy().into_owned()
+}
+
+/// Returns the path of the `index`-th shard named after `template`.
+///
+/// `{n}` in the template is replaced with the shard number and `{base}`
+/// with the file stem of `output`, so `sitemaps/{base}-part-{n}.xml` with
+/// an output of `sitemap.xml` gives `sitemaps/sitemap-part-1.xml`.
 ///
-/// # Errors
+/// # Arguments
 ///
-/// This function will return an error if:
-/// - The file cannot be opened
-/// - There are issues reading lines from the file
-/// - Any of the URLs in the file are invalid
-pub fn read_urls_from_file(filename: &str) -> SitemapResult<Vec<Url>> {
-    let file = File::open(filename).map_err(SitemapError::IoError)?;
-    let reader = io::BufReader::new(file);
+/// * `template` - The shard naming pattern
+/// * `output` - The requested output path
+/// * `index` - The 1-based shard number
+pub fn template_shard_path(
+    template: &str,
+    output: &str,
+    index: usize,
+) -> String {
+    let base = Path::new(output)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy())
+        .unwrap_or_default();
+    template
+        .replace("{base}", &base)
+        .replace("{n}", &index.to_string())
+}
 
-    reader
-        .lines()
-        .enumerate()
-        .filter_map(|(index, line)| {
-            let line = line.ok()?;
-            if line.trim().is_empty() {
-                return None;
-            }
-            match Url::parse(&line) {
-                Ok(url) => Some(Ok(url)),
-                Err(e) => {
-                    warn!(
-                        "Invalid URL on line {}: '{}'. Error: {}",
-                        index + 1,
-                        line,
-                        e
-                    );
-                    Some(Err(SitemapError::UrlError(e)))
-                }
+/// Checks that a shard naming template tells the shards apart.
+fn check_output_template(template: &str) -> SitemapResult<()> {
+    if template.contains("{n}") {
+        Ok(())
+    } else {
+        Err(SitemapError::CustomError(format!(
+            "Output template '{}' must contain {{n}}",
+            template
+        )))
+    }
+}
+
+/// Checks that the shards of a split sitemap all share a single host.
+fn assert_single_host(shards: &[Sitemap]) -> SitemapResult<()> {
+    let mut hosts = Vec::new();
+    for shard in shards {
+        shard.assert_single_host()?;
+        if let Some(host) = shard.hosts().into_iter().next() {
+            if !hosts.contains(&host) {
+                hosts.push(host);
             }
-        })
-        .collect()
+        }
+    }
+    if hosts.len() > 1 {
+        return Err(SitemapError::CustomError(format!(
+            "Sitemap for {} also contains other hosts: {}",
+            hosts[0],
+            hosts[1..].join(", ")
+        )));
+    }
+    Ok(())
 }
 
-/// Normalizes a list of URLs to avoid duplicates.
+/// A human-readable summary of a sitemap generation run.
 ///
-/// This function removes URL fragments and ensures each URL ends with a trailing slash
-/// if it doesn't have a path or if the path is just "/".
-/// It also logs a warning if duplicate URLs are found after normalization.
-/// Invalid URLs (those not using http or https schemes) are filtered out.
+/// The report is written next to the sitemap when the `--report` option is
+/// used, giving operators an audit trail of what was generated.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenerationReport {
+    /// The path of the generated sitemap.
+    pub output: String,
+    /// The number of URLs included in the sitemap.
+    pub url_count: usize,
+    /// The size of the generated sitemap in bytes.
+    pub byte_size: usize,
+    /// The number of sitemap files written.
+    pub shard_count: usize,
+    /// URLs skipped because their scheme is not supported.
+    pub skipped: Vec<Url>,
+    /// URLs dropped as duplicates after normalization.
+    pub duplicates: Vec<Url>,
+    /// The time at which the sitemap was generated.
+    pub generated_at: String,
+}
+
+impl fmt::Display for GenerationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "sitemap-gen {}", env!("CARGO_PKG_VERSION"))?;
+        writeln!(f, "Generated at: {}", self.generated_at)?;
+        writeln!(f, "Output: {}", self.output)?;
+        writeln!(f, "URLs: {}", self.url_count)?;
+        writeln!(f, "Size: {} bytes", self.byte_size)?;
+        writeln!(f, "Shards: {}", self.shard_count)?;
+        writeln!(f, "Skipped URLs: {}", self.skipped.len())?;
+        for url in &self.skipped {
+            writeln!(f, "  {}", url)?;
+        }
+        if !self.skipped.is_empty() {
+            writeln!(
+                f,
+                "Skipped by scheme: {}",
+                describe_scheme_tally(&tally_schemes(&self.skipped))
+            )?;
+        }
+        writeln!(f, "Duplicate URLs: {}", self.duplicates.len())?;
+        for url in &self.duplicates {
+            writeln!(f, "  {}", url)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses a change frequency alias given as `ALIAS=FREQ`.
 ///
 /// # Arguments
 ///
-/// * `urls` - A vector of URLs to normalize
+/// * `spec` - The alias specification, e.g. `nightly=daily`
 ///
-/// # Returns
+/// # Errors
 ///
-/// A vector of normalized unique URLs
-pub fn normalize_urls(urls: Vec<Url>) -> Vec<Url> {
-    let mut normalized = HashSet::new();
-    for mut url in urls {
-        if !is_valid_url(&url) {
-            warn!("Invalid URL scheme: {}", url);
-            continue;
-        }
-        url.set_fragment(None);
-        if url.path().is_empty() || url.path() == "/" {
-            url.set_path("/");
-        }
-        if !normalized.insert(url.clone()) {
-            warn!("Duplicate URL found after normalization: {}", url);
+/// This function will return an error if:
+/// - The specification is not of the form `ALIAS=FREQ`
+/// - `FREQ` is not a valid change frequency
+pub fn parse_changefreq_alias(
+    spec: &str,
+) -> SitemapResult<(&str, ChangeFreq)> {
+    match spec.split_once('=') {
+        Some((alias, freq)) if !alias.trim().is_empty() => {
+            Ok((alias.trim(), freq.trim().parse()?))
         }
+        _ => Err(SitemapError::CustomError(format!(
+            "Invalid change frequency alias '{}'. Expected ALIAS=FREQ.",
+            spec
+        ))),
     }
-    normalized.into_iter().collect()
 }
 
-/// Checks if a URL is valid for inclusion in the sitemap.
+/// Expands an input path that may contain `*` or `?` wildcards.
 ///
-/// This function checks if the URL uses either the HTTP or HTTPS scheme.
+/// Wildcards are only supported in the file name, so `urls/*.txt` matches
+/// the `.txt` files directly inside `urls`. A path without wildcards is
+/// returned as is, whether or not it exists, so that opening it reports
+/// the usual error.
 ///
 /// # Arguments
 ///
-/// * `url` - The URL to validate
+/// * `pattern` - The input path or pattern
 ///
 /// # Returns
 ///
-/// `true` if the URL is valid, `false` otherwise
-pub fn is_valid_url(url: &Url) -> bool {
-    matches!(url.scheme(), "http" | "https")
+/// The matching paths, sorted by name
+///
+/// # Errors
+///
+/// This function will return an error if the pattern's directory cannot be
+/// read or no file matches.
+pub fn expand_input_pattern(
+    pattern: &str,
+) -> SitemapResult<Vec<PathBuf>> {
+    let path = Path::new(pattern);
+    let name = match path.file_name().and_then(|name| name.to_str()) {
+        Some(name) if name.contains(['*', '?']) => name,
+        _ => return Ok(vec![path.to_path_buf()]),
+    };
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    let mut matches = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let matched = entry
+            .file_name()
+            .to_str()
+            .map_or(false, |file| wildcard_match(name, file));
+        if matched && entry.file_type()?.is_file() {
+            matches.push(entry.path());
+        }
+    }
+    if matches.is_empty() {
+        return Err(SitemapError::CustomError(format!(
+            "No input files match '{}'",
+            pattern
+        )));
+    }
+    matches.sort();
+    Ok(matches)
 }
 
-/// Writes the sitemap XML to an output file.
+/// Matches `name` against a pattern where `*` stands for any run of
+/// characters and `?` for a single character.
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, start)) => {
+                    p = star + 1;
+                    n = start + 1;
+                    backtrack = Some((star, start + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Reads URLs from a file, one URL per line.
+///
+/// Blank lines and comment lines starting with `#` are ignored. A
+/// `filename` of `-` reads from stdin instead.
 ///
 /// # Arguments
 ///
-/// * `xml` - The XML content to write
-/// * `output_file` - The name of the output file
+/// * `filename` - The name of the file to read URLs from, or `-`
 ///
 /// # Errors
 ///
 /// This function will return an error if:
-/// - The output file cannot be created
-/// - There are issues writing to the file
-pub fn write_output(xml: &str, output_file: &str) -> SitemapResult<()> {
-    let mut file =
-        File::create(output_file).map_err(SitemapError::IoError)?;
-    file.write_all(xml.as_bytes())
-        .map_err(SitemapError::IoError)?;
-    Ok(())
+/// - The file cannot be opened
+/// - There are issues reading lines from the file
+/// - Any of the URLs in the file are invalid
+pub fn read_urls_from_file(filename: &str) -> SitemapResult<Vec<Url>> {
+    if filename == "-" {
+        let stdin = io::stdin();
+        return parse_urls_from_reader(stdin.lock());
+    }
+    let file = File::open(filename).map_err(SitemapError::IoError)?;
+    parse_urls_from_reader(io::BufReader::new(file))
 }
 
-/// Formats a DateTime object into a string suitable for sitemap use.
+/// Parses URLs from any buffered reader, one URL per line.
+///
+/// This allows URLs to be read from stdin, a network stream or an in-memory
+/// buffer. Blank lines and comment lines starting with `#` are ignored.
 ///
 /// # Arguments
 ///
-/// * `dt` - The DateTime object to format
+/// * `reader` - The source to read URLs from
 ///
-/// # Returns
+/// # Errors
 ///
-/// A string representation of the date in YYYY-MM-DD format
-pub fn format_date(dt: DateTime) -> String {
-    dt.format("[year]-[month]-[day]")
-        .unwrap_or_else(|_| "".to_string())
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// This function will return an error if:
+/// - There are issues reading lines from the reader
+/// - Any of the URLs are invalid
+pub fn parse_urls_from_reader<R: BufRead>(
+    reader: R,
+) -> SitemapResult<Vec<Url>> {
+    reader
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => return Some(Err(SitemapError::IoError(e))),
+            };
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                return None;
+            }
+            match Url::parse(&line) {
+                Ok(url) => Some(Ok(url)),
+                Err(e) => {
+                    warn!(
+                        "Invalid URL on line {}: '{}'. Error: {}",
+                        index + 1,
+                        line,
+                        e
+                    );
+                    Some(Err(SitemapError::UrlError(e)))
+                }
+            }
+        })
+        .collect()
+}
+
+/// Column positions resolved from a CSV header row.
+#[derive(Debug, Clone, Copy)]
+struct CsvColumns {
+    count: usize,
+    loc: usize,
+    lastmod: Option<usize>,
+    changefreq: Option<usize>,
+    priority: Option<usize>,
+}
+
+impl CsvColumns {
+    /// Maps header names to column positions.
+    ///
+    /// `url` (or `loc`) is required; `lastmod`, `freq` (or `changefreq`) and
+    /// `priority` are optional. Names are case-insensitive.
+    fn from_header(header: &str) -> SitemapResult<Self> {
+        let mut loc = None;
+        let mut lastmod = None;
+        let mut changefreq = None;
+        let mut priority = None;
+
+        let names: Vec<String> = split_csv_record(header)
+            .ok_or_else(|| {
+                SitemapError::CustomError(
+                    "Malformed CSV header: unterminated quoted field"
+                        .to_string(),
+                )
+            })?
+            .iter()
+            .map(|name| name.to_lowercase())
+            .collect();
+        for (index, name) in names.iter().enumerate() {
+            let slot = match name.as_str() {
+                "url" | "loc" => &mut loc,
+                "lastmod" => &mut lastmod,
+                "freq" | "changefreq" => &mut changefreq,
+                "priority" => &mut priority,
+                "" => {
+                    return Err(SitemapError::CustomError(format!(
+                        "Malformed CSV header: column {} has no name",
+                        index + 1
+                    )))
+                }
+                other => {
+                    return Err(SitemapError::CustomError(format!(
+                        "Malformed CSV header: unknown column '{}'",
+                        other
+                    )))
+                }
+            };
+            if slot.replace(index).is_some() {
+                return Err(SitemapError::CustomError(format!(
+                    "Malformed CSV header: duplicate column '{}'",
+                    name
+                )));
+            }
+        }
+
+        let loc = loc.ok_or_else(|| {
+            SitemapError::CustomError(
+                "Malformed CSV header: missing required 'url' column"
+                    .to_string(),
+            )
+        })?;
+        Ok(CsvColumns {
+            count: names.len(),
+            loc,
+            lastmod,
+            changefreq,
+            priority,
+        })
+    }
+}
+
+/// Splits a CSV record into its fields.
+///
+/// A field may be wrapped in double quotes to hold commas, with `""`
+/// standing for a literal quote. Whitespace around unquoted fields is
+/// trimmed. Returns `None` if a quoted field is unterminated or followed by
+/// anything but a comma.
+fn split_csv_record(line: &str) -> Option<Vec<String>> {
+    let finish = |field: &mut String, quoted: bool| {
+        let field = std::mem::take(field);
+        if quoted {
+            field
+        } else {
+            field.trim().to_string()
+        }
+    };
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    let _ = chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                c => field.push(c),
+            }
+            continue;
+        }
+        match c {
+            ',' => {
+                fields.push(finish(&mut field, quoted));
+                quoted = false;
+            }
+            '"' if !quoted && field.trim().is_empty() => {
+                field.clear();
+                quoted = true;
+                in_quotes = true;
+            }
+            c if quoted && !c.is_whitespace() => return None,
+            _ if quoted => {}
+            c => field.push(c),
+        }
+    }
+    if in_quotes {
+        return None;
+    }
+    fields.push(finish(&mut field, quoted));
+    Some(fields)
+}
+
+/// Parses sitemap entries from CSV with a header row.
+///
+/// Columns are matched by name, so their order is flexible. Fields may be
+/// quoted to hold commas. The `url` (or
+/// `loc`) column is required. Missing optional columns fall back to defaults:
+/// today's date for `lastmod` and `default_changefreq` for `freq` (or
+/// `changefreq`). A `priority` column must hold a value between 0.0 and
+/// 1.0; entries without one have no priority.
+///
+/// # Arguments
+///
+/// * `reader` - The source to read CSV data from
+/// * `default_changefreq` - The change frequency used when the column is absent or empty
+///
+/// # Errors
+///
+/// This function will return an error if:
+/// - The header is missing or malformed
+/// - A row has a different number of fields than the header
+/// - A field holds an invalid URL, change frequency or priority
+pub fn parse_csv_entries<R: BufRead>(
+    reader: R,
+    default_changefreq: ChangeFreq,
+) -> SitemapResult<Vec<SiteMapData>> {
+    let mut lines = reader.lines().enumerate();
+    let columns = match lines.next() {
+        Some((_, line)) => CsvColumns::from_header(&line?)?,
+        None => {
+            return Err(SitemapError::CustomError(
+                "Malformed CSV: missing header row".to_string(),
+            ))
+        }
+    };
+
+    let mut entries = Vec::new();
+    for (index, line) in lines {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let row_error = |message: String| {
+            SitemapError::CustomError(format!(
+                "CSV line {}: {}",
+                index + 1,
+                message
+            ))
+        };
+
+        let fields = split_csv_record(&line).ok_or_else(|| {
+            row_error("malformed quoted field".to_string())
+        })?;
+        if fields.len() != columns.count {
+            return Err(row_error(format!(
+                "expected {} fields, found {}",
+                columns.count,
+                fields.len()
+            )));
+        }
+        let field = |column: Option<usize>| {
+            column
+                .map(|i| fields[i].as_str())
+                .filter(|value| !value.is_empty())
+        };
+
+        let loc = Url::parse(&fields[columns.loc])
+            .map_err(|e| row_error(format!("invalid URL: {}", e)))?;
+        let changefreq = match field(columns.changefreq) {
+            Some(value) => value
+                .parse()
+                .map_err(|e: SitemapError| row_error(e.to_string()))?,
+            None => default_changefreq,
+        };
+        let priority = match field(columns.priority) {
+            Some(value) => match value.parse::<f32>() {
+                Ok(priority) if (0.0..=1.0).contains(&priority) => {
+                    Some(priority)
+                }
+                _ => {
+                    return Err(row_error(format!(
+                        "invalid priority '{}'",
+                        value
+                    )))
+                }
+            },
+            None => None,
+        };
+        let lastmod = field(columns.lastmod)
+            .map(str::to_string)
+            .unwrap_or_else(|| format_date(dtt_now!()));
+
+        entries.push(SiteMapData {
+            changefreq,
+            lastmod,
+            loc,
+            priority,
+            extra: Vec::new(),
+            images: Vec::new(),
+            videos: Vec::new(),
+            alternates: Vec::new(),
+        });
+    }
+    Ok(entries)
+}
+
+/// Normalizes a list of URLs to avoid duplicates.
+///
+/// This function removes URL fragments and ensures each URL ends with a trailing slash
+/// if it doesn't have a path or if the path is just "/".
+/// Tracking and session parameters from [`DEFAULT_TRACKING_PARAMS`] are
+/// removed from the query. It also logs a warning if duplicate URLs are found after normalization.
+/// Invalid URLs (those not using http or https schemes) are filtered out.
+/// The remaining URLs keep the order in which they were first seen, so the
+/// same input always produces the same output.
+///
+/// # Arguments
+///
+/// * `urls` - A vector of URLs to normalize
+///
+/// # Returns
+///
+/// A vector of normalized unique URLs
+pub fn normalize_urls(urls: Vec<Url>) -> Vec<Url> {
+    normalize_urls_with_report(urls).0
+}
+
+/// Query parameters removed by default during normalization.
+///
+/// A trailing `*` matches any parameter starting with the prefix before it.
+/// Names are compared case-insensitively.
+pub const DEFAULT_TRACKING_PARAMS: &[&str] =
+    &["utm_*", "fbclid", "gclid", "sessionid", "PHPSESSID"];
+
+/// Options controlling URL normalization.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NormalizeOptions {
+    /// Query parameters to remove, using the same patterns as
+    /// [`DEFAULT_TRACKING_PARAMS`].
+    pub strip_params: Vec<String>,
+}
+
+impl Default for NormalizeOptions {
+    /// Strips the [`DEFAULT_TRACKING_PARAMS`].
+    fn default() -> Self {
+        NormalizeOptions {
+            strip_params: DEFAULT_TRACKING_PARAMS
+                .iter()
+                .map(|param| param.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl NormalizeOptions {
+    /// Returns options that keep every query parameter.
+    pub fn keep_all_params() -> Self {
+        NormalizeOptions {
+            strip_params: Vec::new(),
+        }
+    }
+
+    /// Checks if the query parameter `name` should be removed.
+    fn strips(&self, name: &str) -> bool {
+        self.strip_params.iter().any(|pattern| {
+            match pattern.strip_suffix('*') {
+                Some(prefix) => {
+                    name.get(..prefix.len()).map_or(false, |start| {
+                        start.eq_ignore_ascii_case(prefix)
+                    })
+                }
+                None => name.eq_ignore_ascii_case(pattern),
+            }
+        })
+    }
+}
+
+/// Computes the canonical form of a single URL.
+///
+/// This is the per-URL step applied by [`normalize_urls`]: the fragment is
+/// removed, an empty path is replaced with `/`, and the
+/// [`DEFAULT_TRACKING_PARAMS`] are removed from the query.
+///
+/// # Arguments
+///
+/// * `url` - The URL to normalize
+///
+/// # Returns
+///
+/// The normalized URL, or `None` if the URL doesn't use the http or https scheme
+pub fn normalize_url(url: Url) -> Option<Url> {
+    normalize_url_with(url, &NormalizeOptions::default())
+}
+
+/// Computes the canonical form of a single URL with the given options.
+///
+/// # Arguments
+///
+/// * `url` - The URL to normalize
+/// * `options` - The normalization options
+///
+/// # Returns
+///
+/// The normalized URL, or `None` if the URL doesn't use the http or https scheme
+pub fn normalize_url_with(
+    mut url: Url,
+    options: &NormalizeOptions,
+) -> Option<Url> {
+    if !is_valid_url(&url) {
+        return None;
+    }
+    url.set_fragment(None);
+    if url.path().is_empty() || url.path() == "/" {
+        url.set_path("/");
+    }
+    if let Some(query) = url.query() {
+        // Filter the raw pairs, so the encoding of kept parameters is untouched.
+        let kept: Vec<&str> = query
+            .split('&')
+            .filter(|pair| {
+                let name = pair.split('=').next().unwrap_or_default();
+                !pair.is_empty() && !options.strips(name)
+            })
+            .collect();
+        let kept = kept.join("&");
+        if kept.is_empty() {
+            url.set_query(None);
+        } else if kept != query {
+            url.set_query(Some(&kept));
+        }
+    }
+    Some(url)
+}
+
+/// Records the URLs dropped while normalizing a list of URLs.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct NormalizeReport {
+    /// URLs skipped because they don't use the http or https scheme.
+    pub skipped: Vec<Url>,
+    /// URLs dropped because they duplicate an earlier URL after normalization.
+    pub duplicates: Vec<Url>,
+}
+
+impl NormalizeReport {
+    /// Counts the skipped URLs per scheme.
+    ///
+    /// # Returns
+    ///
+    /// `(scheme, count)` pairs, most frequent first and then by scheme name
+    pub fn skipped_by_scheme(&self) -> Vec<(String, usize)> {
+        tally_schemes(&self.skipped)
+    }
+}
+
+/// Counts `urls` per scheme, most frequent first and then by scheme name.
+fn tally_schemes(urls: &[Url]) -> Vec<(String, usize)> {
+    let mut tally: Vec<(String, usize)> = Vec::new();
+    for url in urls {
+        match tally
+            .iter_mut()
+            .find(|(scheme, _)| scheme == url.scheme())
+        {
+            Some((_, count)) => *count += 1,
+            None => tally.push((url.scheme().to_string(), 1)),
+        }
+    }
+    tally.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    tally
+}
+
+/// Describes a scheme tally, e.g. `3 ftp URLs, 1 file URL`.
+fn describe_scheme_tally(tally: &[(String, usize)]) -> String {
+    tally
+        .iter()
+        .map(|(scheme, count)| {
+            let noun = if *count == 1 { "URL" } else { "URLs" };
+            format!("{} {} {}", count, scheme, noun)
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Normalizes a list of URLs, reporting which URLs were dropped.
+///
+/// This behaves exactly like [`normalize_urls`], but also returns a
+/// [`NormalizeReport`] listing the skipped and duplicate URLs.
+///
+/// # Arguments
+///
+/// * `urls` - A vector of URLs to normalize
+///
+/// # Returns
+///
+/// A tuple of the normalized unique URLs and the normalization report
+pub fn normalize_urls_with_report(
+    urls: Vec<Url>,
+) -> (Vec<Url>, NormalizeReport) {
+    normalize_urls_with_options(urls, &NormalizeOptions::default())
+}
+
+/// Normalizes a list of URLs with the given options, reporting which URLs
+/// were dropped.
+///
+/// # Arguments
+///
+/// * `urls` - A vector of URLs to normalize
+/// * `options` - The normalization options
+///
+/// # Returns
+///
+/// A tuple of the normalized unique URLs and the normalization report
+pub fn normalize_urls_with_options(
+    urls: Vec<Url>,
+    options: &NormalizeOptions,
+) -> (Vec<Url>, NormalizeReport) {
+    let mut normalized = Vec::new();
+    let mut seen = HashSet::new();
+    let mut report = NormalizeReport::default();
+    for url in urls {
+        let url = match normalize_url_with(url.clone(), options) {
+            Some(normalized) => normalized,
+            None => {
+                warn!("Invalid URL scheme: {}", url);
+                report.skipped.push(url);
+                continue;
+            }
+        };
+        if seen.insert(url.clone()) {
+            normalized.push(url);
+        } else {
+            warn!("Duplicate URL found after normalization: {}", url);
+            report.duplicates.push(url);
+        }
+    }
+    if !report.skipped.is_empty() {
+        warn!(
+            "Skipped {}",
+            describe_scheme_tally(&report.skipped_by_scheme())
+        );
+    }
+    (normalized, report)
+}
+
+/// Lazily normalizes and deduplicates a stream of URLs.
+///
+/// Applies the same rules as [`normalize_urls`], but yields each unique URL
+/// as soon as it is seen, in first-seen order, so large inputs are not
+/// collected into an intermediate list. Only the set of URLs seen so far is
+/// kept in memory. Invalid URLs and duplicates are skipped with a warning.
+///
+/// # Arguments
+///
+/// * `urls` - An iterator of URLs to normalize
+///
+/// # Returns
+///
+/// An iterator over the normalized unique URLs
+pub fn normalize_urls_iter<I: Iterator<Item = Url>>(
+    urls: I,
+) -> impl Iterator<Item = Url> {
+    let options = NormalizeOptions::default();
+    let mut seen = HashSet::new();
+    urls.filter_map(move |url| {
+        let normalized = match normalize_url_with(url.clone(), &options)
+        {
+            Some(normalized) => normalized,
+            None => {
+                warn!("Invalid URL scheme: {}", url);
+                return None;
+            }
+        };
+        if seen.insert(normalized.clone()) {
+            Some(normalized)
+        } else {
+            warn!(
+                "Duplicate URL found after normalization: {}",
+                normalized
+            );
+            None
+        }
+    })
+}
+
+/// Filters out URLs with unsupported schemes, leaving the rest untouched.
+///
+/// Used instead of [`normalize_urls_with_report`] when normalization is
+/// disabled, so fragments and trailing-slash forms are kept as given.
+fn retain_valid_urls(urls: Vec<Url>) -> (Vec<Url>, NormalizeReport) {
+    let mut report = NormalizeReport::default();
+    let urls = urls
+        .into_iter()
+        .filter(|url| {
+            let valid = is_valid_url(url);
+            if !valid {
+                warn!("Invalid URL scheme: {}", url);
+                report.skipped.push(url.clone());
+            }
+            valid
+        })
+        .collect();
+    (urls, report)
+}
+
+/// Checks if a URL is valid for inclusion in the sitemap.
+///
+/// This function checks if the URL uses either the HTTP or HTTPS scheme.
+/// `Url` always stores its scheme in lowercase, whether it was parsed or set
+/// with `Url::set_scheme`, so `HTTPS://example.com` is accepted as well.
+/// Opaque URLs that cannot be a base, such as `data:` or `mailto:` URLs, are
+/// rejected, which also keeps them away from path and fragment edits during
+/// normalization.
+///
+/// # Arguments
+///
+/// * `url` - The URL to validate
+///
+/// # Returns
+///
+/// `true` if the URL is valid, `false` otherwise
+pub fn is_valid_url(url: &Url) -> bool {
+    !url.cannot_be_a_base() && matches!(url.scheme(), "http" | "https")
+}
+
+/// Loads the `Disallow` rules that apply to all crawlers from a
+/// `robots.txt` file.
+///
+/// Only the groups whose `User-agent` is `*` are read, and empty
+/// `Disallow` lines, which allow everything, are skipped. `path_or_url`
+/// may be a local path or a `file://` URL; remote files are not fetched.
+///
+/// # Arguments
+///
+/// * `path_or_url` - The path or `file://` URL of the `robots.txt` file
+///
+/// # Returns
+///
+/// The disallowed path patterns, in file order
+///
+/// # Errors
+///
+/// This function will return an error if:
+/// - `path_or_url` is a URL with a scheme other than `file`
+/// - The file cannot be read
+pub fn load_robots_disallow(
+    path_or_url: &str,
+) -> SitemapResult<Vec<String>> {
+    let path = match Url::parse(path_or_url) {
+        Ok(url) if url.scheme() == "file" => {
+            url.to_file_path().map_err(|()| {
+                SitemapError::CustomError(format!(
+                    "Invalid file URL '{}'",
+                    path_or_url
+                ))
+            })?
+        }
+        // A single letter is a Windows drive, not a scheme.
+        Ok(url) if url.scheme().len() > 1 => {
+            return Err(SitemapError::CustomError(format!(
+                "Cannot load robots.txt from '{}': only local files are supported",
+                path_or_url
+            )));
+        }
+        _ => PathBuf::from(path_or_url),
+    };
+    let contents = std::fs::read_to_string(path)?;
+    Ok(parse_robots_disallow(&contents))
+}
+
+/// Parses the `Disallow` rules of the `User-agent: *` groups.
+fn parse_robots_disallow(contents: &str) -> Vec<String> {
+    let mut rules = Vec::new();
+    let (mut applies, mut in_agents) = (false, false);
+    for line in contents.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        let (field, value) = match line.split_once(':') {
+            Some(pair) => pair,
+            None => continue,
+        };
+        let value = value.trim();
+        if field.trim().eq_ignore_ascii_case("user-agent") {
+            // Consecutive user-agent lines share the group that follows.
+            applies = (in_agents && applies) || value == "*";
+            in_agents = true;
+            continue;
+        }
+        in_agents = false;
+        if applies
+            && field.trim().eq_ignore_ascii_case("disallow")
+            && !value.is_empty()
+        {
+            rules.push(value.to_string());
+        }
+    }
+    rules
+}
+
+/// Checks whether `url` is disallowed by any of the `robots.txt` rules.
+///
+/// Rules match the start of the URL's path and query. A `*` in a rule
+/// stands for any run of characters, and a trailing `$` anchors the rule
+/// to the end of the URL.
+///
+/// # Arguments
+///
+/// * `url` - The URL to check
+/// * `rules` - The rules, as returned by [`load_robots_disallow`]
+pub fn is_disallowed(url: &Url, rules: &[String]) -> bool {
+    let target = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    };
+    rules.iter().any(|rule| robots_rule_matches(rule, &target))
+}
+
+/// Drops the URLs disallowed by any of the `robots.txt` rules.
+///
+/// # Arguments
+///
+/// * `urls` - The URLs to filter
+/// * `rules` - The rules, as returned by [`load_robots_disallow`]
+///
+/// # Returns
+///
+/// The allowed URLs, in their original order
+pub fn filter_disallowed(urls: Vec<Url>, rules: &[String]) -> Vec<Url> {
+    urls.into_iter()
+        .filter(|url| !is_disallowed(url, rules))
+        .collect()
+}
+
+/// Matches `target` against a `robots.txt` path rule.
+fn robots_rule_matches(rule: &str, target: &str) -> bool {
+    let (rule, anchored) = match rule.strip_suffix('$') {
+        Some(rule) => (rule, true),
+        None => (rule, false),
+    };
+    let mut parts = rule.split('*');
+    let first = parts.next().unwrap_or_default();
+    let mut rest = match target.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let parts: Vec<&str> = parts.collect();
+    for (index, part) in parts.iter().enumerate() {
+        let last = index + 1 == parts.len();
+        if last && anchored {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+    !anchored || rest.is_empty()
+}
+
+/// Generates a `robots.txt` that points crawlers at the given sitemaps.
+///
+/// The file holds a `User-agent: *` line followed by one `Sitemap:` line
+/// per sitemap. Duplicate URLs are listed once, and URLs that are not
+/// absolute http(s) URLs are skipped, as crawlers ignore them.
+///
+/// # Arguments
+///
+/// * `sitemap_urls` - The public URLs of the sitemaps
+///
+/// # Returns
+///
+/// The contents of the `robots.txt` file
+pub fn generate_robots_txt(sitemap_urls: &[Url]) -> String {
+    let mut robots = String::from("User-agent: *\n");
+    let mut seen = HashSet::new();
+    for url in sitemap_urls {
+        if !is_valid_url(url) {
+            warn!("Skipping non-http(s) sitemap URL: {}", url);
+            continue;
+        }
+        if seen.insert(url.as_str()) {
+            robots.push_str(&format!("Sitemap: {}\n", url));
+        }
+    }
+    robots
+}
+
+/// Writes the sitemap XML to an output file.
+///
+/// # Arguments
+///
+/// * `xml` - The XML content to write
+/// * `output_file` - The name of the output file
+///
+/// # Errors
+///
+/// This function will return an error if:
+/// - The output file cannot be created
+/// - There are issues writing to the file
+pub fn write_output(xml: &str, output_file: &str) -> SitemapResult<()> {
+    let mut file =
+        File::create(output_file).map_err(SitemapError::IoError)?;
+    write_outputs(xml, &mut [&mut file])
+}
+
+/// Writes the sitemap XML to each of several writers, such as a file and
+/// stdout.
+///
+/// # Arguments
+///
+/// * `xml` - The XML content to write
+/// * `writers` - The writers to write the XML to, in order
+///
+/// # Errors
+///
+/// This function will return an error if writing to or flushing any of
+/// the writers fails. Writers after the failing one are left untouched.
+pub fn write_outputs(
+    xml: &str,
+    writers: &mut [&mut dyn Write],
+) -> SitemapResult<()> {
+    for writer in writers.iter_mut() {
+        writer
+            .write_all(xml.as_bytes())
+            .map_err(SitemapError::IoError)?;
+        writer.flush().map_err(SitemapError::IoError)?;
+    }
+    Ok(())
+}
+
+/// Writes a gzip-compressed sitemap, such as the output of
+/// [`Sitemap::to_xml_gzip`], to an output file.
+///
+/// The bytes are written as given, so `output_file` would normally end in
+/// `.xml.gz`.
+///
+/// Requires the `gzip` feature.
+///
+/// # Arguments
+///
+/// * `bytes` - The compressed sitemap
+/// * `output_file` - The name of the output file
+///
+/// # Errors
+///
+/// This function will return an error if:
+/// - The output file cannot be created
+/// - There are issues writing to the file
+#[cfg(feature = "gzip")]
+pub fn write_output_gz(
+    bytes: &[u8],
+    output_file: &str,
+) -> SitemapResult<()> {
+    let mut file =
+        File::create(output_file).map_err(SitemapError::IoError)?;
+    file.write_all(bytes).map_err(SitemapError::IoError)?;
+    Ok(())
+}
+
+/// The format description used for `lastmod` dates.
+const DATE_FORMAT: &str = "[year]-[month]-[day]";
+
+/// Formats a DateTime object into a string suitable for sitemap use.
+///
+/// If formatting fails, a warning is logged and the date is built from
+/// its calendar fields instead, so the result is never empty. Use
+/// [`try_format_date`] to handle the error instead.
+///
+/// # Arguments
+///
+/// * `dt` - The DateTime object to format
+///
+/// # Returns
+///
+/// A string representation of the date in YYYY-MM-DD format
+pub fn format_date(dt: DateTime) -> String {
+    format_date_or_fallback(dt, DATE_FORMAT)
+}
+
+/// Formats a DateTime object into a string suitable for sitemap use,
+/// returning an error if formatting fails.
+///
+/// # Arguments
+///
+/// * `dt` - The DateTime object to format
+///
+/// # Returns
+///
+/// The date in YYYY-MM-DD format, or `SitemapError::DateError`
+pub fn try_format_date(dt: DateTime) -> SitemapResult<String> {
+    Ok(dt.format(DATE_FORMAT)?)
+}
+
+/// Formats `dt` with `format`, falling back to its calendar fields in
+/// YYYY-MM-DD format if that fails.
+fn format_date_or_fallback(dt: DateTime, format: &str) -> String {
+    dt.format(format).unwrap_or_else(|error| {
+        let fallback = format!(
+            "{:04}-{:02}-{:02}",
+            dt.year(),
+            u8::from(dt.month()),
+            dt.day()
+        );
+        warn!(
+            "Failed to format date {}: {}; using {}",
+            dt, error, fallback
+        );
+        fallback
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
     use crate::utils::{
         is_valid_url, normalize_urls, read_urls_from_file,
     };
@@ -303,6 +1807,18 @@ mod tests {
     use tempfile::NamedTempFile;
     use url::Url;
 
+    #[test]
+    fn test_format_date_falls_back_on_error() -> SitemapResult<()> {
+        let dt = DateTime::parse("2024-10-08")?;
+        assert_eq!(format_date(dt), "2024-10-08");
+        assert_eq!(try_format_date(dt)?, "2024-10-08");
+        assert_eq!(
+            format_date_or_fallback(dt, "[not a component]"),
+            "2024-10-08"
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_read_urls_from_file() -> SitemapResult<()> {
         let mut temp_file =
@@ -325,6 +1841,176 @@ fn test_read_urls_from_file() -> SitemapResult<()> {
         Ok(())
     }
 
+    #[test]
+    fn test_parse_urls_from_reader() -> SitemapResult<()> {
+        let input = io::Cursor::new(
+            "https://example.com\nhttps://example.org\n",
+        );
+        let urls = parse_urls_from_reader(input)?;
+        assert_eq!(urls.len(), 2);
+        assert_eq!(urls[0].as_str(), "https://example.com/");
+        assert_eq!(urls[1].as_str(), "https://example.org/");
+        Ok(())
+    }
+
+    #[test]
+    fn test_normalize_urls_iter_is_lazy_and_ordered() {
+        let inputs = [
+            "https://example.com/b#top",
+            "https://example.com/a",
+            "ftp://example.com/file",
+            "https://example.com/b",
+            "https://example.com/c?utm_source=x",
+            "https://example.com/a",
+        ];
+        let pulled = std::cell::Cell::new(0);
+        let urls = inputs.iter().map(|url| {
+            pulled.set(pulled.get() + 1);
+            Url::parse(url).unwrap()
+        });
+
+        let mut normalized = normalize_urls_iter(urls);
+        assert_eq!(
+            normalized.next().map(String::from),
+            Some("https://example.com/b".to_string())
+        );
+        assert_eq!(pulled.get(), 1);
+        let rest: Vec<String> = normalized.map(String::from).collect();
+        assert_eq!(
+            rest,
+            ["https://example.com/a", "https://example.com/c"]
+        );
+        assert_eq!(pulled.get(), inputs.len());
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match("*.txt", "urls.txt"));
+        assert!(wildcard_match("urls-?.txt", "urls-1.txt"));
+        assert!(wildcard_match("a*b*c", "aXbYbZc"));
+        assert!(!wildcard_match("*.txt", "urls.csv"));
+        assert!(!wildcard_match("urls-?.txt", "urls-10.txt"));
+    }
+
+    #[cfg(feature = "gzip")]
+    #[test]
+    fn test_write_output_gz() -> SitemapResult<()> {
+        use std::io::Read;
+
+        let mut sitemap = Sitemap::new();
+        sitemap.add_url(Url::parse("https://example.com/")?)?;
+        let dir = tempfile::TempDir::new()?;
+        let path = dir.path().join("sitemap.xml.gz");
+        let path = path.to_str().unwrap();
+
+        write_output_gz(&sitemap.to_xml_gzip()?, path)?;
+
+        let mut xml = String::new();
+        let _ = flate2::read::GzDecoder::new(File::open(path)?)
+            .read_to_string(&mut xml)?;
+        assert_eq!(xml, sitemap.to_xml()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_csv_entries_maps_columns_by_name() -> SitemapResult<()>
+    {
+        let input = io::Cursor::new(
+            "priority,url,freq\n0.8,https://example.com,daily\n,https://example.com/blog,\n",
+        );
+        let entries = parse_csv_entries(input, ChangeFreq::Weekly)?;
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].loc.as_str(), "https://example.com/");
+        assert_eq!(entries[0].changefreq, ChangeFreq::Daily);
+        assert_eq!(entries[0].priority, Some(0.8));
+        assert_eq!(entries[1].loc.as_str(), "https://example.com/blog");
+        assert_eq!(entries[1].priority, None);
+        assert_eq!(entries[1].changefreq, ChangeFreq::Weekly);
+        assert_eq!(entries[1].lastmod, format_date(dtt_now!()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_csv_entries_rejects_malformed_header() {
+        for header in
+            ["freq,priority", "url,url", "url,,freq", "url,size"]
+        {
+            let input = io::Cursor::new(format!("{}\n", header));
+            let result = parse_csv_entries(input, ChangeFreq::Weekly);
+            assert!(
+                matches!(&result, Err(SitemapError::CustomError(msg)) if msg.starts_with("Malformed CSV header")),
+                "{}: {:?}",
+                header,
+                result
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_csv_entries_handles_quoted_fields(
+    ) -> SitemapResult<()> {
+        let input = io::Cursor::new(
+            "loc,lastmod,changefreq,priority\n\
+             \"https://example.com/a,b\", 2024-01-02 ,daily,0.8\n\
+             https://example.com/c,,,\n\
+             \"https://example.com/d,2024-01-02,daily,0.8\n",
+        );
+        let result = parse_csv_entries(input, ChangeFreq::Weekly);
+        assert!(matches!(
+            &result,
+            Err(SitemapError::CustomError(msg))
+                if msg == "CSV line 4: malformed quoted field"
+        ));
+
+        assert_eq!(
+            split_csv_record(r#"a, "b ""c"", d" ,e"#),
+            Some(vec![
+                "a".to_string(),
+                "b \"c\", d".to_string(),
+                "e".to_string()
+            ])
+        );
+        assert_eq!(split_csv_record(r#""a"b,c"#), None);
+
+        let input = io::Cursor::new(
+            "loc,lastmod,changefreq,priority\n\
+             \"https://example.com/a,b\",2024-01-02,daily,0.8\n\
+             https://example.com/c,,,\n",
+        );
+        let entries = parse_csv_entries(input, ChangeFreq::Weekly)?;
+        assert_eq!(entries[0].loc.as_str(), "https://example.com/a,b");
+        assert_eq!(entries[0].lastmod, "2024-01-02");
+        assert_eq!(entries[0].changefreq, ChangeFreq::Daily);
+        assert_eq!(entries[0].priority, Some(0.8));
+        assert_eq!(entries[1].changefreq, ChangeFreq::Weekly);
+        assert_eq!(entries[1].priority, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_urls_skips_comments() -> SitemapResult<()> {
+        let mut temp_file =
+            NamedTempFile::new().map_err(SitemapError::IoError)?;
+        writeln!(temp_file, "# Marketing pages")
+            .map_err(SitemapError::IoError)?;
+        writeln!(temp_file, "https://example.com")
+            .map_err(SitemapError::IoError)?;
+        writeln!(temp_file).map_err(SitemapError::IoError)?;
+        writeln!(temp_file, "   # indented comment")
+            .map_err(SitemapError::IoError)?;
+        writeln!(temp_file, "https://example.com/about")
+            .map_err(SitemapError::IoError)?;
+
+        let urls =
+            read_urls_from_file(temp_file.path().to_str().unwrap())?;
+        assert_eq!(urls.len(), 2);
+        assert_eq!(urls[0].as_str(), "https://example.com/");
+        assert_eq!(urls[1].as_str(), "https://example.com/about");
+
+        Ok(())
+    }
+
     #[test]
     fn test_invalid_url_in_file() {
         let mut temp_file = NamedTempFile::new().unwrap();
@@ -357,6 +2043,89 @@ fn test_normalize_urls() {
             .contains(&Url::parse("ftp://example.net").unwrap()));
     }
 
+    #[test]
+    fn test_normalize_urls_preserves_first_seen_order() {
+        let urls: Vec<Url> = [
+            "https://example.com/zebra",
+            "https://example.com/apple#top",
+            "https://example.com/zebra",
+            "https://example.com/mango",
+            "https://example.com/apple",
+        ]
+        .iter()
+        .map(|url| Url::parse(url).unwrap())
+        .collect();
+
+        let normalized = normalize_urls(urls.clone());
+        let paths: Vec<&str> =
+            normalized.iter().map(Url::path).collect();
+        assert_eq!(paths, ["/zebra", "/apple", "/mango"]);
+        assert_eq!(normalize_urls(urls), normalized);
+    }
+
+    #[test]
+    fn test_normalize_url() {
+        assert_eq!(
+            normalize_url(
+                Url::parse("https://example.com/page#section").unwrap()
+            ),
+            Some(Url::parse("https://example.com/page").unwrap())
+        );
+        assert_eq!(
+            normalize_url(Url::parse("https://example.com").unwrap())
+                .unwrap()
+                .as_str(),
+            "https://example.com/"
+        );
+        assert_eq!(
+            normalize_url(
+                Url::parse("ftp://example.com/file").unwrap()
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_normalize_urls_with_report() {
+        let urls = vec![
+            Url::parse("http://example.com").unwrap(),
+            Url::parse("http://example.com/#top").unwrap(),
+            Url::parse("ftp://example.net").unwrap(),
+        ];
+
+        let (normalized, report) = normalize_urls_with_report(urls);
+        assert_eq!(normalized.len(), 1);
+        assert_eq!(
+            report.skipped,
+            vec![Url::parse("ftp://example.net").unwrap()]
+        );
+        assert_eq!(
+            report.duplicates,
+            vec![Url::parse("http://example.com/").unwrap()]
+        );
+    }
+
+    #[test]
+    fn test_generation_report_display() {
+        let report = GenerationReport {
+            output: "sitemap.xml".to_string(),
+            url_count: 2,
+            byte_size: 512,
+            shard_count: 1,
+            skipped: vec![Url::parse("ftp://example.net").unwrap()],
+            duplicates: Vec::new(),
+            generated_at: "2024-10-08T00:00:00Z".to_string(),
+        };
+
+        let text = report.to_string();
+        assert!(text.contains(env!("CARGO_PKG_VERSION")));
+        assert!(text.contains("URLs: 2"));
+        assert!(text.contains("Size: 512 bytes"));
+        assert!(text.contains("Skipped URLs: 1\n  ftp://example.net"));
+        assert!(text.contains("Skipped by scheme: 1 ftp URL"));
+        assert!(text.contains("Duplicate URLs: 0"));
+    }
+
     #[test]
     fn test_is_valid_url() {
         assert!(is_valid_url(
@@ -370,6 +2139,104 @@ fn test_is_valid_url() {
         ));
     }
 
+    #[test]
+    fn test_uppercase_scheme_is_accepted() {
+        let url = Url::parse("HTTPS://example.com").unwrap();
+        assert!(is_valid_url(&url));
+        assert_eq!(
+            normalize_url(url).unwrap().as_str(),
+            "https://example.com/"
+        );
+
+        let mut url = Url::parse("http://example.com/page").unwrap();
+        url.set_scheme("HTTPS").unwrap();
+        assert!(is_valid_url(&url));
+        assert_eq!(url.scheme(), "https");
+    }
+
+    #[test]
+    fn test_shard_path() {
+        assert_eq!(shard_path("sitemap.xml", 1), "sitemap-1.xml");
+        assert_eq!(shard_path("out/site.xml", 12), "out/site-12.xml");
+        assert_eq!(shard_path("sitemap", 2), "sitemap-2");
+        assert_eq!(
+            template_shard_path(
+                "maps/{base}-part-{n}.xml",
+                "out/site.xml",
+                3
+            ),
+            "maps/site-part-3.xml"
+        );
+        assert!(check_output_template("part.xml").is_err());
+    }
+
+    #[test]
+    fn test_opaque_urls_are_rejected() {
+        for input in ["data:text/plain,hi", "mailto:team@example.com"] {
+            let url = Url::parse(input).unwrap();
+            assert!(url.cannot_be_a_base());
+            assert!(!is_valid_url(&url));
+            assert_eq!(normalize_url(url.clone()), None);
+            assert!(normalize_urls(vec![url]).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_normalize_strips_tracking_params() {
+        let urls = vec![
+            Url::parse("https://example.com/page?utm_source=x&id=1")
+                .unwrap(),
+            Url::parse("https://example.com/page?fbclid=y&id=1")
+                .unwrap(),
+            Url::parse("https://example.com/page?PHPSESSID=abc")
+                .unwrap(),
+        ];
+        let (normalized, report) = normalize_urls_with_report(urls);
+
+        let mut locs: Vec<&str> =
+            normalized.iter().map(Url::as_str).collect();
+        locs.sort_unstable();
+        assert_eq!(
+            locs,
+            [
+                "https://example.com/page",
+                "https://example.com/page?id=1"
+            ]
+        );
+        assert_eq!(report.duplicates.len(), 1);
+
+        let kept = normalize_url_with(
+            Url::parse("https://example.com/?utm_source=x").unwrap(),
+            &NormalizeOptions::keep_all_params(),
+        )
+        .unwrap();
+        assert_eq!(kept.as_str(), "https://example.com/?utm_source=x");
+    }
+
+    #[test]
+    fn test_skipped_by_scheme() {
+        let urls = [
+            "ftp://example.com/a",
+            "file:///tmp/page.html",
+            "https://example.com/",
+            "ftp://example.com/b",
+            "ftp://example.com/c",
+        ]
+        .iter()
+        .map(|url| Url::parse(url).unwrap())
+        .collect();
+
+        let (_, report) = normalize_urls_with_report(urls);
+        assert_eq!(
+            report.skipped_by_scheme(),
+            [("ftp".to_string(), 3), ("file".to_string(), 1)]
+        );
+        assert_eq!(
+            describe_scheme_tally(&report.skipped_by_scheme()),
+            "3 ftp URLs, 1 file URL"
+        );
+    }
+
     #[test]
     fn test_empty_file() -> SitemapResult<()> {
         let temp_file =
@@ -423,6 +2290,117 @@ fn test_invalid_change_frequency() {
         assert!(result.is_err(), "Parsing an invalid change frequency should return an error");
     }
 
+    #[test]
+    fn test_parse_changefreq_alias() -> SitemapResult<()> {
+        assert_eq!(
+            parse_changefreq_alias("nightly=daily")?,
+            ("nightly", ChangeFreq::Daily)
+        );
+        assert!(parse_changefreq_alias("nightly").is_err());
+        assert!(parse_changefreq_alias("=daily").is_err());
+        assert!(parse_changefreq_alias("nightly=sometimes").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_robots_disallow_filters_urls() -> SitemapResult<()> {
+        let mut robots = NamedTempFile::new()?;
+        writeln!(
+            robots,
+            "User-agent: Googlebot\nDisallow: /\n\n\
+             User-agent: *\nDisallow: /private/ # staff only\n\
+             Disallow: /*.pdf$\nDisallow:\n"
+        )?;
+        let rules =
+            load_robots_disallow(robots.path().to_str().unwrap())?;
+        assert_eq!(rules, vec!["/private/", "/*.pdf$"]);
+
+        let urls = [
+            "https://example.com/",
+            "https://example.com/private/notes",
+            "https://example.com/privacy",
+            "https://example.com/docs/guide.pdf",
+            "https://example.com/docs/guide.pdf?download=1",
+        ]
+        .iter()
+        .map(|url| Url::parse(url))
+        .collect::<Result<Vec<_>, _>>()?;
+        let allowed: Vec<String> = filter_disallowed(urls, &rules)
+            .iter()
+            .map(Url::to_string)
+            .collect();
+        assert_eq!(
+            allowed,
+            vec![
+                "https://example.com/",
+                "https://example.com/privacy",
+                "https://example.com/docs/guide.pdf?download=1",
+            ]
+        );
+
+        assert!(load_robots_disallow("https://example.com/robots.txt")
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate_robots_txt() -> SitemapResult<()> {
+        let urls = [
+            "https://example.com/sitemap-1.xml",
+            "https://example.com/sitemap-2.xml",
+            "https://example.com/sitemap-1.xml",
+            "ftp://example.com/sitemap.xml",
+        ]
+        .iter()
+        .map(|url| Url::parse(url))
+        .collect::<Result<Vec<_>, _>>()?;
+
+        assert_eq!(
+            generate_robots_txt(&urls),
+            "User-agent: *\n\
+             Sitemap: https://example.com/sitemap-1.xml\n\
+             Sitemap: https://example.com/sitemap-2.xml\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_outputs_to_each_writer() -> SitemapResult<()> {
+        let (mut first, mut second) = (Vec::new(), Vec::new());
+        write_outputs("<urlset/>", &mut [&mut first, &mut second])?;
+        assert_eq!(first, b"<urlset/>");
+        assert_eq!(second, b"<urlset/>");
+        Ok(())
+    }
+
+    #[test]
+    fn test_cancelled_generation_removes_only_its_files(
+    ) -> SitemapResult<()> {
+        let dir = tempfile::TempDir::new()?;
+        let path = |name: &str| {
+            dir.path().join(name).to_str().unwrap().to_string()
+        };
+        let (written, earlier) = (path("sitemap.xml"), path("old.xml"));
+        write_output("<urlset/>", &earlier)?;
+
+        let outputs = GenerationOutputs::default();
+        outputs
+            .create(&written, || write_output("<urlset/>", &written))?;
+        outputs.cancel();
+
+        assert!(!Path::new(&written).exists());
+        assert!(Path::new(&earlier).exists());
+        let result =
+            outputs.create(&written, || write_output("", &written));
+        assert!(matches!(
+            result,
+            Err(SitemapError::CustomError(message))
+                if message == "generation timed out"
+        ));
+        assert!(!Path::new(&written).exists());
+        Ok(())
+    }
+
     #[test]
     fn test_write_output_file() -> SitemapResult<()> {
         let temp_file =
@@ -547,7 +2525,7 @@ fn test_url_special_characters() {
     #[test]
     fn test_io_failure_during_write() {
         // Simulate an I/O error when attempting to write to a non-writable location
-        let unwritable_path = "/root/unwritable_output.xml";
+        let unwritable_path = "/nonexistent_dir/unwritable_output.xml";
 
         let sample_xml =
             "<urlset><url><loc>http://example.com</loc></url></urlset>";
@@ -588,6 +2566,11 @@ fn test_concurrent_sitemap_generation() -> SitemapResult<()> {
                             loc: url.clone(),
                             lastmod: "2024-01-01".to_string(),
                             changefreq: ChangeFreq::Weekly,
+                            priority: None,
+                            extra: Vec::new(),
+                            images: Vec::new(),
+                            videos: Vec::new(),
+                            alternates: Vec::new(),
                         };
                         sitemap.add_entry(entry).unwrap();
                     }
@@ -1 +1,578 @@
+#![allow(missing_docs)]
 
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+use tempfile::TempDir;
+
+/// Runs the `sitemap-gen` binary with the given arguments.
+fn run_cli(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_sitemap-gen"))
+        .args(args)
+        .output()
+        .expect("Failed to execute sitemap-gen")
+}
+
+/// Returns the given path as a `&str`.
+fn path_str(path: &Path) -> &str {
+    path.to_str().expect("Path is not valid UTF-8")
+}
+
+#[test]
+fn test_generate_writes_report() {
+    let dir = TempDir::new().unwrap();
+    let output = dir.path().join("sitemap.xml");
+    let report = dir.path().join("report.txt");
+
+    let result = run_cli(&[
+        "generate",
+        "-o",
+        path_str(&output),
+        "-u",
+        "https://example.com",
+        "-u",
+        "https://example.com/about",
+        "--report",
+        path_str(&report),
+    ]);
+
+    assert!(result.status.success());
+    let report = fs::read_to_string(report).unwrap();
+    assert!(report.contains("URLs: 2"));
+    assert!(report.contains(env!("CARGO_PKG_VERSION")));
+}
+
+#[test]
+fn test_generate_respects_limit() {
+    let dir = TempDir::new().unwrap();
+    let input = dir.path().join("urls.txt");
+    let output = dir.path().join("sitemap.xml");
+    let urls: Vec<String> = (0..100)
+        .map(|i| format!("https://example.com/page-{}", i))
+        .collect();
+    fs::write(&input, urls.join("\n")).unwrap();
+
+    let result = run_cli(&[
+        "generate",
+        "-o",
+        path_str(&output),
+        "-i",
+        path_str(&input),
+        "--limit",
+        "10",
+    ]);
+
+    assert!(result.status.success());
+    let xml = fs::read_to_string(&output).unwrap();
+    assert_eq!(xml.matches("<url>").count(), 10);
+
+    fs::remove_file(&output).unwrap();
+    let result = run_cli(&[
+        "generate",
+        "-o",
+        path_str(&output),
+        "-i",
+        path_str(&input),
+        "--limit",
+        "0",
+    ]);
+    assert!(!result.status.success());
+    assert!(!output.exists());
+}
+
+#[test]
+fn test_generate_fails_on_duplicates() {
+    let dir = TempDir::new().unwrap();
+    let input = dir.path().join("urls.txt");
+    let output = dir.path().join("sitemap.xml");
+    fs::write(
+        &input,
+        "https://example.com/a\nhttps://example.com/b\nhttps://example.com/a#top\n",
+    )
+    .unwrap();
+
+    let result = run_cli(&[
+        "generate",
+        "-o",
+        path_str(&output),
+        "-i",
+        path_str(&input),
+        "--fail-on-duplicates",
+    ]);
+
+    assert!(!result.status.success());
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    assert!(stderr.contains("Found 1 duplicate URL(s)"));
+    assert!(stderr.contains("https://example.com/a"));
+    assert!(!output.exists());
+}
+
+#[test]
+fn test_generate_warns_on_empty_input() {
+    let dir = TempDir::new().unwrap();
+    let input = dir.path().join("empty.txt");
+    let output = dir.path().join("sitemap.xml");
+    fs::write(&input, "").unwrap();
+
+    let result = Command::new(env!("CARGO_BIN_EXE_sitemap-gen"))
+        .args([
+            "generate",
+            "-o",
+            path_str(&output),
+            "-i",
+            path_str(&input),
+        ])
+        .env("RUST_LOG", "warn")
+        .output()
+        .expect("Failed to execute sitemap-gen");
+    assert!(result.status.success());
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    assert!(stderr.contains("No URLs to add to the sitemap"));
+
+    let result = run_cli(&[
+        "generate",
+        "-o",
+        path_str(&output),
+        "-i",
+        path_str(&input),
+        "--fail-on-empty",
+    ]);
+    assert!(!result.status.success());
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    assert!(stderr.contains("Check that the input contains valid"));
+}
+
+#[test]
+fn test_generate_no_normalize_keeps_fragments() {
+    let dir = TempDir::new().unwrap();
+    let output = dir.path().join("sitemap.xml");
+
+    let result = run_cli(&[
+        "generate",
+        "-o",
+        path_str(&output),
+        "-u",
+        "https://example.com/docs#install",
+        "--no-normalize",
+    ]);
+
+    assert!(result.status.success());
+    let xml = fs::read_to_string(output).unwrap();
+    assert!(xml.contains("<loc>https://example.com/docs#install</loc>"));
+}
+
+#[test]
+fn test_generate_pretty_uses_indent_size() {
+    let dir = TempDir::new().unwrap();
+    let output = dir.path().join("sitemap.xml");
+
+    let result = run_cli(&[
+        "generate",
+        "-o",
+        path_str(&output),
+        "-u",
+        "https://example.com",
+        "--pretty",
+        "--indent-size",
+        "4",
+    ]);
+
+    assert!(result.status.success());
+    let xml = fs::read_to_string(&output).unwrap();
+    assert!(xml.contains("\n    <url>\n        <loc>"));
+
+    let result = run_cli(&[
+        "generate",
+        "-o",
+        path_str(&output),
+        "-u",
+        "https://example.com",
+        "--pretty",
+        "--indent-size",
+        "9",
+    ]);
+    assert!(!result.status.success());
+}
+
+#[test]
+fn test_generate_reads_input_glob() {
+    let dir = TempDir::new().unwrap();
+    let urls = dir.path().join("urls");
+    fs::create_dir(&urls).unwrap();
+    fs::write(urls.join("a.txt"), "https://example.com/a\n").unwrap();
+    fs::write(urls.join("b.txt"), "https://example.com/b\n").unwrap();
+    fs::write(urls.join("c.csv"), "https://example.com/c\n").unwrap();
+    let output = dir.path().join("sitemap.xml");
+
+    let pattern = urls.join("*.txt");
+    let result = run_cli(&[
+        "generate",
+        "-o",
+        path_str(&output),
+        "-i",
+        path_str(&pattern),
+    ]);
+
+    assert!(result.status.success());
+    let xml = fs::read_to_string(&output).unwrap();
+    assert_eq!(xml.matches("<url>").count(), 2);
+    assert!(xml.contains("https://example.com/a"));
+    assert!(xml.contains("https://example.com/b"));
+
+    let pattern = urls.join("*.json");
+    let result = run_cli(&[
+        "generate",
+        "-o",
+        path_str(&output),
+        "-i",
+        path_str(&pattern),
+    ]);
+    assert!(!result.status.success());
+    assert!(String::from_utf8_lossy(&result.stderr)
+        .contains("No input files match"));
+}
+
+#[test]
+fn test_generate_single_host_rejects_mixed_hosts() {
+    let dir = TempDir::new().unwrap();
+    let output = dir.path().join("sitemap.xml");
+
+    let result = run_cli(&[
+        "generate",
+        "-o",
+        path_str(&output),
+        "-u",
+        "https://example.com",
+        "-u",
+        "https://example.org",
+        "--single-host",
+    ]);
+
+    assert!(!result.status.success());
+    assert!(
+        String::from_utf8_lossy(&result.stderr).contains("example.org")
+    );
+    assert!(!output.exists());
+}
+
+#[test]
+fn test_generate_splits_at_threshold() {
+    let dir = TempDir::new().unwrap();
+    let input = dir.path().join("urls.txt");
+    let output = dir.path().join("sitemap.xml");
+    let urls: Vec<String> = (0..45_000)
+        .map(|i| format!("https://example.com/page-{}", i))
+        .collect();
+    fs::write(&input, urls.join("\n")).unwrap();
+
+    let result = run_cli(&[
+        "generate",
+        "-o",
+        path_str(&output),
+        "-i",
+        path_str(&input),
+        "--split-threshold",
+        "40000",
+    ]);
+
+    assert!(result.status.success());
+    assert!(!output.exists());
+    let first =
+        fs::read_to_string(dir.path().join("sitemap-1.xml")).unwrap();
+    let second =
+        fs::read_to_string(dir.path().join("sitemap-2.xml")).unwrap();
+    assert_eq!(first.matches("<url>").count(), 40_000);
+    assert_eq!(second.matches("<url>").count(), 5_000);
+    assert!(!dir.path().join("sitemap-3.xml").exists());
+}
+
+#[test]
+fn test_generate_output_template_names_shards() {
+    let dir = TempDir::new().unwrap();
+    let output = dir.path().join("sitemap.xml");
+    let template = dir.path().join("{base}-part-{n}.xml");
+
+    let mut args = vec![
+        "generate",
+        "-o",
+        path_str(&output),
+        "--split-threshold",
+        "2",
+        "--output-template",
+        path_str(&template),
+    ];
+    let urls: Vec<String> = (0..3)
+        .map(|i| format!("https://example.com/{}", i))
+        .collect();
+    for url in &urls {
+        args.extend(["-u", url.as_str()]);
+    }
+    let result = run_cli(&args);
+
+    assert!(result.status.success());
+    let first =
+        fs::read_to_string(dir.path().join("sitemap-part-1.xml"))
+            .unwrap();
+    let second =
+        fs::read_to_string(dir.path().join("sitemap-part-2.xml"))
+            .unwrap();
+    assert_eq!(first.matches("<url>").count(), 2);
+    assert_eq!(second.matches("<url>").count(), 1);
+    assert!(!dir.path().join("sitemap-1.xml").exists());
+
+    let result = run_cli(&[
+        "generate",
+        "-o",
+        path_str(&output),
+        "-u",
+        "https://example.com",
+        "--split-threshold",
+        "2",
+        "--output-template",
+        "part.xml",
+    ]);
+    assert!(!result.status.success());
+    assert!(String::from_utf8_lossy(&result.stderr).contains("{n}"));
+}
+
+#[test]
+fn test_generate_also_stdout_echoes_sitemap() {
+    let dir = TempDir::new().unwrap();
+    let output = dir.path().join("sitemap.xml");
+
+    let result = run_cli(&[
+        "generate",
+        "-o",
+        path_str(&output),
+        "-u",
+        "https://example.com/about",
+        "--also-stdout",
+    ]);
+
+    assert!(result.status.success());
+    let written = fs::read_to_string(&output).unwrap();
+    assert!(written.contains("<loc>https://example.com/about</loc>"));
+    assert_eq!(String::from_utf8_lossy(&result.stdout), written);
+}
+
+#[test]
+fn test_generate_writes_robots_txt() {
+    let dir = TempDir::new().unwrap();
+    let output = dir.path().join("sitemap.xml");
+    let robots = dir.path().join("robots.txt");
+
+    let result = run_cli(&[
+        "generate",
+        "-o",
+        path_str(&output),
+        "-u",
+        "https://example.com/about",
+        "--robots",
+        path_str(&robots),
+    ]);
+
+    assert!(result.status.success());
+    assert_eq!(
+        fs::read_to_string(robots).unwrap(),
+        "User-agent: *\nSitemap: https://example.com/sitemap.xml\n"
+    );
+}
+
+#[test]
+fn test_generate_sets_priority() {
+    let dir = TempDir::new().unwrap();
+    let output = dir.path().join("sitemap.xml");
+
+    let result = run_cli(&[
+        "generate",
+        "-o",
+        path_str(&output),
+        "-u",
+        "https://example.com",
+        "-p",
+        "0.9",
+    ]);
+
+    assert!(result.status.success());
+    let written = fs::read_to_string(&output).unwrap();
+    assert!(written.contains("<priority>0.9</priority>"));
+
+    for invalid in ["1.5", "high"] {
+        let result = run_cli(&[
+            "generate",
+            "-o",
+            path_str(&output),
+            "-u",
+            "https://example.com",
+            "--priority",
+            invalid,
+        ]);
+        assert!(!result.status.success());
+        assert!(
+            String::from_utf8_lossy(&result.stderr).contains(invalid)
+        );
+    }
+}
+
+#[test]
+fn test_generate_reads_csv_metadata() {
+    let dir = TempDir::new().unwrap();
+    let input = dir.path().join("urls.csv");
+    let output = dir.path().join("sitemap.xml");
+    fs::write(
+        &input,
+        "loc,lastmod,changefreq,priority\n\
+         https://example.com/news,2024-01-02,daily,0.8\n\
+         https://example.com/about,,,\n",
+    )
+    .unwrap();
+
+    let result = run_cli(&[
+        "generate",
+        "-o",
+        path_str(&output),
+        "--csv",
+        path_str(&input),
+    ]);
+
+    assert!(result.status.success());
+    let written = fs::read_to_string(&output).unwrap();
+    assert!(written.contains(
+        "<loc>https://example.com/news</loc>\
+         <lastmod>2024-01-02</lastmod>\
+         <changefreq>daily</changefreq>\
+         <priority>0.8</priority>"
+    ));
+    assert!(written
+        .contains("<loc>https://example.com/about</loc><lastmod>"));
+    assert!(!written.contains("<lastmod></lastmod>"));
+
+    fs::write(&input, "loc,priority\nhttps://example.com/,high\n")
+        .unwrap();
+    let result = run_cli(&[
+        "generate",
+        "-o",
+        path_str(&output),
+        "--csv",
+        path_str(&input),
+    ]);
+    assert!(!result.status.success());
+    assert!(
+        String::from_utf8_lossy(&result.stderr).contains("CSV line 2")
+    );
+}
+
+#[test]
+fn test_generate_output_formats() {
+    let dir = TempDir::new().unwrap();
+    let output = dir.path().join("sitemap.txt");
+    let generate = |format: &str| {
+        run_cli(&[
+            "generate",
+            "-o",
+            path_str(&output),
+            "-u",
+            "https://example.com",
+            "-u",
+            "https://example.com/about",
+            "--format",
+            format,
+        ])
+    };
+
+    assert!(generate("txt").status.success());
+    assert_eq!(
+        fs::read_to_string(&output).unwrap(),
+        "https://example.com/\nhttps://example.com/about\n"
+    );
+
+    assert!(!generate("yaml").status.success());
+}
+
+#[test]
+fn test_generate_reads_urls_from_stdin() {
+    let dir = TempDir::new().unwrap();
+    let output = dir.path().join("sitemap.xml");
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_sitemap-gen"))
+        .args(["generate", "-i", "-", "-o", path_str(&output)])
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to execute sitemap-gen");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(
+            b"https://example.com\n\nhttps://example.com/about\n",
+        )
+        .unwrap();
+    let result = child.wait_with_output().unwrap();
+
+    assert!(result.status.success());
+    let written = fs::read_to_string(&output).unwrap();
+    assert!(written.contains("<loc>https://example.com/</loc>"));
+    assert!(written.contains("<loc>https://example.com/about</loc>"));
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_sitemap-gen"))
+        .args(["generate", "-i", "-", "-o", path_str(&output)])
+        .env("RUST_LOG", "warn")
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to execute sitemap-gen");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(b"https://example.com\nnot a url\n")
+        .unwrap();
+    let result = child.wait_with_output().unwrap();
+
+    assert!(!result.status.success());
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    assert!(stderr.contains("Invalid URL on line 2"));
+}
+
+#[test]
+fn test_version_full_reports_versions() {
+    let result = run_cli(&["--version-full"]);
+
+    assert!(result.status.success());
+    let stdout = String::from_utf8_lossy(&result.stdout);
+    assert!(stdout.contains(&format!(
+        "sitemap-gen {}",
+        env!("CARGO_PKG_VERSION")
+    )));
+    assert!(stdout.contains("url "));
+    assert!(stdout.contains("xml-rs "));
+    assert!(stdout.contains("dtt "));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_generate_times_out() {
+    let dir = TempDir::new().unwrap();
+    let input = dir.path().join("urls.fifo");
+    let output = dir.path().join("sitemap.xml");
+    // Reading from a FIFO with no writer blocks, simulating a slow input.
+    let status = Command::new("mkfifo").arg(&input).status().unwrap();
+    assert!(status.success());
+
+    let result = run_cli(&[
+        "generate",
+        "-o",
+        path_str(&output),
+        "-i",
+        path_str(&input),
+        "--timeout",
+        "1",
+    ]);
+
+    assert!(!result.status.success());
+    assert!(String::from_utf8_lossy(&result.stderr)
+        .contains("generation timed out"));
+    assert!(!output.exists());
+}
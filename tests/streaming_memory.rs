@@ -0,0 +1,120 @@
+#![allow(missing_docs)]
+
+//! Compares the peak heap usage of `Sitemap::to_xml` and
+//! `Sitemap::write_to`, and of `Sitemap` and `CompactSitemap` storage.
+//! This lives in its own test binary because the counting allocator sees
+//! every allocation in the process.
+
+use lazy_static::lazy_static;
+use sitemap_gen::{ChangeFreq, CompactSitemap, SiteMapData, Sitemap};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use url::Url;
+
+/// The most entries a single sitemap may hold.
+const MAX_URLS: usize = 50_000;
+
+/// An allocator that tracks the current and peak number of live bytes.
+#[derive(Debug)]
+struct CountingAllocator;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let current = CURRENT
+                .fetch_add(layout.size(), Ordering::SeqCst)
+                + layout.size();
+            let _ = PEAK.fetch_max(current, Ordering::SeqCst);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        let _ = CURRENT.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+lazy_static! {
+    static ref COUNTERS: Mutex<()> = Mutex::new(());
+}
+
+/// Stops tests running at the same time, as they share the counters.
+fn exclusive_counters() -> MutexGuard<'static, ()> {
+    COUNTERS.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Returns the peak number of bytes allocated while running `f`, on top
+/// of what was live when it started.
+fn peak_allocated<T>(f: impl FnOnce() -> T) -> usize {
+    let baseline = CURRENT.load(Ordering::SeqCst);
+    PEAK.store(baseline, Ordering::SeqCst);
+    drop(f());
+    PEAK.load(Ordering::SeqCst) - baseline
+}
+
+/// Returns `count` entries with distinct locations.
+fn entries(count: usize) -> impl Iterator<Item = SiteMapData> {
+    (0..count).map(|i| {
+        SiteMapData::new(
+            Url::parse(&format!("https://example.com/page-{}", i))
+                .unwrap(),
+            "2024-10-08",
+            ChangeFreq::Weekly,
+        )
+    })
+}
+
+#[test]
+fn test_write_to_allocates_less_than_to_xml() {
+    let _counters = exclusive_counters();
+    let mut sitemap = Sitemap::new();
+    for entry in entries(MAX_URLS) {
+        sitemap.add_entry(entry).unwrap();
+    }
+
+    let buffered = peak_allocated(|| sitemap.to_xml().unwrap());
+    let streamed =
+        peak_allocated(|| sitemap.write_to(io::sink()).unwrap());
+
+    // `to_xml` holds the whole document, over 4MB for these entries.
+    assert!(buffered > 4 * 1024 * 1024, "to_xml peak: {}", buffered);
+    assert!(
+        streamed * 100 < buffered,
+        "write_to peak {} vs to_xml peak {}",
+        streamed,
+        buffered
+    );
+}
+
+#[test]
+fn test_compact_storage_uses_less_memory() {
+    let _counters = exclusive_counters();
+    // Both grow one entry at a time, as when reading a URL list.
+    let urls = peak_allocated(|| {
+        let mut urls = Vec::new();
+        entries(MAX_URLS).for_each(|entry| urls.push(entry));
+        urls
+    });
+    let compact = peak_allocated(|| {
+        let mut compact = CompactSitemap::new();
+        entries(MAX_URLS).for_each(|entry| compact.add_entry(entry));
+        compact
+    });
+
+    assert!(
+        compact < urls,
+        "compact peak {} vs Url peak {}",
+        compact,
+        urls
+    );
+}